@@ -0,0 +1,53 @@
+-- Label of an alternating timetable week (the <WEEK> of OrarioFacile)
+CREATE TYPE week_label AS ENUM ('A', 'B');
+
+-- NULL means the row applies to every week
+ALTER TABLE lesson
+    ADD COLUMN week week_label;
+
+ALTER TABLE availability
+    ADD COLUMN week week_label;
+
+-- Monday of a week that counts as week A, weeks alternate A/B from there.
+-- NULL means the import does not alternate.
+ALTER TABLE import
+    ADD COLUMN week_a_start DATE
+        CHECK (EXTRACT(ISODOW FROM week_a_start) = 1);
+
+-- Explicit calendar week -> A/B mapping, overrides the plain alternation
+-- (e.g. when holidays shift the cycle)
+CREATE TABLE import_week
+(
+    import_id  INTEGER REFERENCES import (id) ON DELETE CASCADE NOT NULL,
+    week_start DATE                                             NOT NULL
+        CHECK (EXTRACT(ISODOW FROM week_start) = 1),
+    week       week_label                                       NOT NULL,
+    PRIMARY KEY (import_id, week_start)
+);
+
+-- Week label in force on a date for an import, NULL if the import does not alternate
+CREATE OR REPLACE FUNCTION import_week_on(p_import_id INTEGER, p_date DATE)
+    RETURNS week_label AS
+$$
+SELECT COALESCE(
+               (SELECT iw.week
+                FROM import_week iw
+                WHERE iw.import_id = p_import_id
+                  AND iw.week_start = DATE_TRUNC('week', p_date)::date),
+               (SELECT CASE
+                           WHEN ((DATE_TRUNC('week', p_date)::date - i.week_a_start) / 7) % 2 = 0
+                               THEN 'A'::week_label
+                           ELSE 'B'::week_label
+                           END
+                FROM import i
+                WHERE i.id = p_import_id
+                  AND i.week_a_start IS NOT NULL)
+       );
+$$ LANGUAGE sql STABLE;
+
+-- Whether a lesson/availability of the given week applies on a date
+CREATE OR REPLACE FUNCTION week_applies(p_week week_label, p_import_id INTEGER, p_date DATE)
+    RETURNS BOOLEAN AS
+$$
+SELECT p_week IS NULL OR COALESCE(p_week = import_week_on(p_import_id, p_date), TRUE);
+$$ LANGUAGE sql STABLE;
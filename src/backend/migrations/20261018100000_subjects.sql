@@ -0,0 +1,9 @@
+CREATE TABLE subject
+(
+    id        SERIAL PRIMARY KEY,
+    name      TEXT                                             NOT NULL,
+    import_id INTEGER REFERENCES import (id) ON DELETE CASCADE NOT NULL
+);
+
+ALTER TABLE lesson
+    ADD COLUMN subject_id INTEGER REFERENCES subject (id) ON DELETE CASCADE;
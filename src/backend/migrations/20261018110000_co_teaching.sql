@@ -0,0 +1,70 @@
+-- A lesson can be held by several teachers (co-teaching, ITP and support
+-- teachers), for several groups (merged classes) in several rooms
+
+CREATE TABLE lesson_teacher
+(
+    lesson_id  INTEGER REFERENCES lesson (id) ON DELETE CASCADE  NOT NULL,
+    teacher_id INTEGER REFERENCES teacher (id) ON DELETE CASCADE NOT NULL,
+    PRIMARY KEY (lesson_id, teacher_id)
+);
+
+CREATE TABLE lesson_group
+(
+    lesson_id INTEGER REFERENCES lesson (id) ON DELETE CASCADE  NOT NULL,
+    group_id  INTEGER REFERENCES "group" (id) ON DELETE CASCADE NOT NULL,
+    PRIMARY KEY (lesson_id, group_id)
+);
+
+CREATE TABLE lesson_room
+(
+    lesson_id INTEGER REFERENCES lesson (id) ON DELETE CASCADE NOT NULL,
+    room_id   INTEGER REFERENCES room (id) ON DELETE CASCADE   NOT NULL,
+    PRIMARY KEY (lesson_id, room_id)
+);
+
+INSERT INTO lesson_teacher (lesson_id, teacher_id)
+SELECT id, teacher_id
+FROM lesson;
+
+INSERT INTO lesson_group (lesson_id, group_id)
+SELECT id, group_id
+FROM lesson
+WHERE group_id IS NOT NULL;
+
+INSERT INTO lesson_room (lesson_id, room_id)
+SELECT id, room_id
+FROM lesson
+WHERE room_id IS NOT NULL;
+
+-- Lessons were tied to their import through their only teacher
+ALTER TABLE lesson
+    ADD COLUMN import_id INTEGER REFERENCES import (id) ON DELETE CASCADE;
+
+UPDATE lesson l
+SET import_id = t.import_id
+FROM teacher t
+WHERE l.teacher_id = t.id;
+
+ALTER TABLE lesson
+    ALTER COLUMN import_id SET NOT NULL;
+
+-- An absence is now of one of the teachers of the lesson
+ALTER TABLE absence
+    ADD COLUMN absent_teacher INTEGER REFERENCES teacher (id) ON DELETE CASCADE;
+
+UPDATE absence ab
+SET absent_teacher = l.teacher_id
+FROM lesson l
+WHERE ab.absent_teacher_lesson = l.id;
+
+ALTER TABLE absence
+    ALTER COLUMN absent_teacher SET NOT NULL,
+    DROP CONSTRAINT absence_absent_teacher_lesson_absence_date_key,
+    ADD UNIQUE (absent_teacher_lesson, absent_teacher, absence_date),
+    ADD FOREIGN KEY (absent_teacher_lesson, absent_teacher)
+        REFERENCES lesson_teacher (lesson_id, teacher_id) ON DELETE CASCADE;
+
+ALTER TABLE lesson
+    DROP COLUMN teacher_id,
+    DROP COLUMN room_id,
+    DROP COLUMN group_id;
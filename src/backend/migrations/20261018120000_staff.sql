@@ -0,0 +1,80 @@
+-- A person working at the school, stable across imports
+CREATE TABLE staff
+(
+    id        SERIAL PRIMARY KEY,
+    user_id   INTEGER REFERENCES "user" (id) ON DELETE CASCADE NOT NULL,
+    full_name TEXT                                             NOT NULL,
+    email     TEXT,
+    phone     TEXT,
+    notes     TEXT
+);
+
+-- Name used to match imported teachers, case and whitespace insensitive
+CREATE OR REPLACE FUNCTION normalize_name(p_name TEXT)
+    RETURNS TEXT AS
+$$
+SELECT UPPER(REGEXP_REPLACE(TRIM(p_name), '\s+', ' ', 'g'));
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Every (normalized) name a staff member appears with in the imports,
+-- e.g. both ROSSI M. and ROSSI MARIO
+CREATE TABLE staff_alias
+(
+    user_id         INTEGER REFERENCES "user" (id) ON DELETE CASCADE NOT NULL,
+    normalized_name TEXT                                             NOT NULL,
+    staff_id        INTEGER REFERENCES staff (id) ON DELETE CASCADE  NOT NULL,
+    PRIMARY KEY (user_id, normalized_name)
+);
+
+ALTER TABLE teacher
+    ADD COLUMN staff_id INTEGER REFERENCES staff (id) ON DELETE SET NULL;
+
+CREATE INDEX teacher_staff_id_idx ON teacher (staff_id);
+
+-- Links the teachers of an import to the staff with the same normalized name,
+-- creating the staff members seen for the first time.
+-- Returns the number of linked teachers.
+CREATE OR REPLACE FUNCTION link_teachers_to_staff(p_import_id INTEGER)
+    RETURNS INTEGER AS
+$$
+DECLARE
+    v_user_id INTEGER;
+    v_linked  INTEGER;
+BEGIN
+    SELECT user_id INTO v_user_id FROM import WHERE id = p_import_id;
+
+    WITH new_names AS (SELECT normalize_name(t.full_name) AS normalized_name,
+                              MIN(t.full_name)            AS full_name
+                       FROM teacher t
+                       WHERE t.import_id = p_import_id
+                         AND NOT EXISTS (SELECT 1
+                                         FROM staff_alias sa
+                                         WHERE sa.user_id = v_user_id
+                                           AND sa.normalized_name = normalize_name(t.full_name))
+                       GROUP BY 1),
+         new_staff AS (
+             INSERT INTO staff (user_id, full_name)
+                 SELECT v_user_id, full_name FROM new_names
+                 RETURNING id, full_name)
+    INSERT
+    INTO staff_alias (user_id, normalized_name, staff_id)
+    SELECT v_user_id, normalize_name(full_name), id
+    FROM new_staff;
+
+    UPDATE teacher t
+    SET staff_id = sa.staff_id
+    FROM staff_alias sa
+    WHERE t.import_id = p_import_id
+      AND sa.user_id = v_user_id
+      AND sa.normalized_name = normalize_name(t.full_name);
+
+    GET DIAGNOSTICS v_linked = ROW_COUNT;
+
+    RETURN v_linked;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Link the teachers imported so far
+SELECT link_teachers_to_staff(id)
+FROM import
+ORDER BY import_ts;
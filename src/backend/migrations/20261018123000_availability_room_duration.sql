@@ -0,0 +1,16 @@
+-- The DISPOSIZIONE# room and the duration of an availability, which were
+-- lost on import, so that an exported import can be imported again as it was
+ALTER TABLE availability
+    ADD COLUMN room_id  INTEGER REFERENCES room (id) ON DELETE CASCADE,
+    ADD COLUMN duration INTERVAL HOUR TO MINUTE NOT NULL DEFAULT INTERVAL '1 hour'
+        CHECK (duration > INTERVAL '0 minutes');
+
+-- Existing availabilities get the first DISPOSIZIONE# room of their import
+UPDATE availability av
+SET room_id = (SELECT r.id
+               FROM room r
+                        JOIN teacher t ON r.import_id = t.import_id
+               WHERE t.id = av.teacher_id
+                 AND r.name LIKE 'DISPOSIZIONE#%'
+               ORDER BY r.name
+               LIMIT 1);
@@ -0,0 +1,11 @@
+-- Imports written by the server are processed in the background
+CREATE TYPE import_status AS ENUM ('Pending', 'Running', 'Failed', 'Done');
+
+ALTER TABLE import
+    -- Existing imports were processed within the request
+    ADD COLUMN status      import_status NOT NULL DEFAULT 'Done',
+    -- Why the import failed
+    ADD COLUMN error       TEXT,
+    -- The ImportOutcome of a successful import, as returned by dry runs
+    ADD COLUMN outcome     JSONB,
+    ADD COLUMN finished_ts TIMESTAMP;
@@ -0,0 +1,18 @@
+-- Set by the user to pick this import over the more recent ones covering the
+-- same dates. Activating an import deactivates the active imports it overlaps.
+ALTER TABLE import
+    ADD COLUMN active BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- The import whose timetable applies on a date: among the completed imports
+-- covering it, the active one, otherwise the most recent
+CREATE OR REPLACE FUNCTION active_import(p_user_id INTEGER, p_date DATE)
+    RETURNS INTEGER AS
+$$
+SELECT i.id
+FROM import i
+WHERE i.user_id = p_user_id
+  AND i.status = 'Done'
+  AND p_date BETWEEN i.begin_ts::date AND i.end_ts::date
+ORDER BY i.active DESC, i.import_ts DESC, i.id DESC
+LIMIT 1;
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,23 @@
+-- An absence over a range of days, expanded into one absence per lesson of
+-- the teacher in the import active on each day
+CREATE TABLE absence_period
+(
+    id         SERIAL PRIMARY KEY,
+    user_id    INTEGER REFERENCES "user" (id) ON DELETE CASCADE NOT NULL,
+    -- The teacher across imports. Merging staff members moves their periods,
+    -- nothing else may drop them.
+    staff_id   INTEGER REFERENCES staff (id) ON DELETE RESTRICT NOT NULL,
+    begin_date DATE                                             NOT NULL,
+    end_date   DATE                                             NOT NULL CHECK (end_date >= begin_date),
+    -- Hours of absence on each day, NULL means the whole day
+    begin_time TIME,
+    end_time   TIME,
+    CHECK ((begin_time IS NULL) = (end_time IS NULL)),
+    CHECK (begin_time <= end_time)
+);
+
+-- Absences created before periods have none
+ALTER TABLE absence
+    ADD COLUMN period_id INTEGER REFERENCES absence_period (id) ON DELETE CASCADE;
+
+CREATE INDEX absence_period_id_idx ON absence (period_id);
@@ -0,0 +1,14 @@
+-- Why a teacher is absent, as accounted by the administrative offices
+CREATE TYPE absence_reason AS ENUM (
+    'Illness',
+    'TrainingCourse',
+    'SchoolTrip',
+    'UnionLeave',
+    'PersonalLeave',
+    'ExamCommission'
+    );
+
+-- NULL when not given, e.g. for the absences added before reasons
+ALTER TABLE absence_period
+    ADD COLUMN reason absence_reason,
+    ADD COLUMN notes  TEXT;
@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use clap::Parser;
+use color_eyre::{Result, eyre::eyre};
+use tracing::info;
+
+use crate::{
+    app::{App, cli::find_user},
+    web::endpoints::protected::import::post::{
+        ImportError, ImportFileMeta, ImportMode, import_local_file,
+    },
+};
+
+#[derive(Parser, Debug)]
+pub struct ImportArgs {
+    /// The timetable: OrarioFacile or aSc XML, CSV or Untis GPU001.TXT, also zipped
+    pub path: PathBuf,
+    /// Username of the owner of the import
+    #[clap(short, long)]
+    pub user: String,
+    /// First day of validity, e.g. 2025-09-15, or a date and time, e.g. 2025-09-15T00:00:00
+    #[clap(long, value_parser = parse_begin_ts)]
+    pub begin: NaiveDateTime,
+    /// Last day of validity, included, or a date and time
+    #[clap(long, value_parser = parse_end_ts)]
+    pub end: NaiveDateTime,
+    /// Any day of a week that is week A, for timetables alternating A/B weeks
+    #[clap(long)]
+    pub week_a_start: Option<NaiveDate>,
+    /// Set to true if you intend to actually writing to the database,
+    /// otherwise only the preview is printed
+    #[clap(short, long)]
+    pub write: bool,
+}
+
+fn parse_begin_ts(value: &str) -> Result<NaiveDateTime, String> {
+    parse_ts(value, NaiveTime::MIN)
+}
+
+fn parse_end_ts(value: &str) -> Result<NaiveDateTime, String> {
+    parse_ts(value, NaiveTime::from_hms_opt(23, 59, 59).expect("valid time"))
+}
+
+/// Dates alone are completed with `time`
+fn parse_ts(value: &str, time: NaiveTime) -> Result<NaiveDateTime, String> {
+    value
+        .parse::<NaiveDateTime>()
+        .or_else(|_| value.parse::<NaiveDate>().map(|date| date.and_time(time)))
+        .map_err(|_| format!("Invalid date: {value}"))
+}
+
+/// Prints the outcome as JSON, like the response of `POST /import`. Unlike
+/// the endpoint, writes are processed right away.
+pub async fn import(args: ImportArgs) -> Result<()> {
+    let db = App::setup_db().await?;
+
+    let user_id = find_user(&db, &args.user).await?;
+
+    let bytes = tokio::fs::read(&args.path).await?;
+
+    let file_name = args
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| eyre!("Not a file: {}", args.path.display()))?;
+
+    let mode = if args.write {
+        ImportMode::Write
+    } else {
+        ImportMode::DryRun
+    };
+
+    let meta = ImportFileMeta::new(file_name, mode, args.begin, args.end, args.week_a_start);
+
+    let res = import_local_file(&db, meta, &bytes, user_id).await;
+
+    db.close().await;
+
+    match res {
+        Ok(outcome) => {
+            println!("{}", sonic_rs::to_string_pretty(&outcome)?);
+
+            if !args.write {
+                info!("Dry run, nothing was written. Use --write to import the file");
+            }
+
+            Ok(())
+        }
+        Err(ImportError::Invalid(report)) => {
+            println!("{}", sonic_rs::to_string_pretty(&report)?);
+
+            Err(eyre!("The file is not valid"))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
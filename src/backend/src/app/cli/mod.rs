@@ -1,4 +1,11 @@
+pub mod import;
+pub mod seed;
+
 use clap::Parser;
+use color_eyre::{Result, eyre::eyre};
+use sqlx::PgPool;
+
+use crate::app::cli::import::ImportArgs;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -11,13 +18,31 @@ pub struct Args {
 #[derive(Parser, Debug)]
 #[command(rename_all = "kebab-case")]
 pub enum Command {
-    /// Sort out the users
+    /// Load a synthetic school for a user, for local development
     SeedLessons(SeedArgs),
+    /// Import a timetable file for a user
+    Import(ImportArgs),
 }
 
 #[derive(Parser, Debug)]
 pub struct SeedArgs {
+    /// Username of the owner of the synthetic school
+    #[clap(short, long)]
+    pub user: String,
     /// Set to true if you intend to actually writing to the database
     #[clap(short, long)]
     pub write: bool,
 }
+
+/// Id of the user with `username`
+async fn find_user(db: &PgPool, username: &str) -> Result<i32> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT id FROM "user" WHERE username = $1
+        "#,
+        username,
+    )
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| eyre!("User {} not found", username))
+}
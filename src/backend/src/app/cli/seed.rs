@@ -0,0 +1,135 @@
+use chrono::{Days, Local, NaiveTime};
+use color_eyre::{Result, eyre::eyre};
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::{
+    app::{
+        App,
+        cli::{SeedArgs, find_user},
+    },
+    fixtures::school::School,
+    types::IsoDow,
+    web::endpoints::protected::import::post::{
+        ImportError, ImportFileMeta, ImportMode, import_local_file,
+    },
+};
+
+/// Weekdays from tomorrow with absences of the synthetic school
+const ABSENCE_DAYS: usize = 5;
+
+/// Imports the synthetic school for the next six months and registers some
+/// absences on the next weekdays, one of them with a substitute.
+pub async fn seed_lessons(args: SeedArgs) -> Result<()> {
+    let db = App::setup_db().await?;
+
+    let user_id = find_user(&db, &args.user).await?;
+
+    let school = School::generate();
+    let csv = school.to_csv()?;
+
+    let today = Local::now().date_naive();
+    let end = today
+        .checked_add_days(Days::new(180))
+        .ok_or_else(|| eyre!("Invalid end date"))?;
+
+    let mode = if args.write {
+        ImportMode::Write
+    } else {
+        ImportMode::DryRun
+    };
+
+    let meta = ImportFileMeta::new(
+        "fixture-school.csv".to_owned(),
+        mode,
+        today.and_time(NaiveTime::MIN),
+        end.and_time(NaiveTime::from_hms_opt(23, 59, 59).expect("valid time")),
+        None,
+    );
+
+    let outcome = match import_local_file(&db, meta, csv.as_bytes(), user_id).await {
+        Ok(outcome) => outcome,
+        Err(ImportError::Invalid(report)) => {
+            println!("{}", sonic_rs::to_string_pretty(&report)?);
+            return Err(eyre!("The fixture is not valid"));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if let Some(import_id) = outcome.import_id() {
+        seed_absences(&db, &school, import_id).await?;
+    } else {
+        info!("Dry run, nothing was written. Use --write to load the school");
+    }
+
+    println!("{}", sonic_rs::to_string_pretty(&outcome)?);
+
+    db.close().await;
+
+    Ok(())
+}
+
+async fn seed_absences(db: &PgPool, school: &School, import_id: i32) -> Result<()> {
+    let mut txn = db.begin().await?;
+
+    let weekdays = Local::now()
+        .date_naive()
+        .iter_days()
+        .skip(1)
+        .filter(|date| !matches!(IsoDow::from(*date), IsoDow::Sat | IsoDow::Sun))
+        .take(ABSENCE_DAYS);
+
+    for (index, date) in weekdays.enumerate() {
+        let day = IsoDow::from(date);
+
+        let absences = sqlx::query_scalar!(
+            r#"
+            INSERT INTO absence (absent_teacher_lesson, absent_teacher, absence_date)
+            SELECT l.id, t.id, $2
+            FROM lesson l
+                     JOIN lesson_teacher lt ON lt.lesson_id = l.id
+                     JOIN teacher t ON lt.teacher_id = t.id
+            WHERE l.import_id = $1
+              AND l.day = $3::smallint
+              AND t.full_name = ANY ($4)
+            ORDER BY l.time
+            RETURNING id
+            "#,
+            import_id,
+            date,
+            day.iso_dow(),
+            school.absent_teachers(index),
+        )
+        .fetch_all(&mut *txn)
+        .await?;
+
+        // Cover the first absence of the day with any teacher available then
+        if let Some(absence_id) = absences.first() {
+            sqlx::query!(
+                r#"
+                UPDATE absence ab
+                SET status                          = 'SubstituteFound',
+                    substitute_teacher_availability = av.id
+                FROM lesson l,
+                     availability av
+                         JOIN teacher t ON av.teacher_id = t.id
+                WHERE ab.id = $1
+                  AND l.id = ab.absent_teacher_lesson
+                  AND t.import_id = $2
+                  AND av.day = l.day
+                  AND av.time = l.time
+                "#,
+                absence_id,
+                import_id,
+            )
+            .execute(&mut *txn)
+            .await?;
+        }
+
+        info!("Seeded {} absences on {}", absences.len(), date);
+    }
+
+    txn.commit().await?;
+
+    Ok(())
+}
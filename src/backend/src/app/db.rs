@@ -4,7 +4,7 @@ use tracing::info;
 use crate::app::App;
 
 impl App {
-    pub(super) async fn setup_db() -> color_eyre::Result<PgPool> {
+    pub(crate) async fn setup_db() -> color_eyre::Result<PgPool> {
         info!("SQLx: Connecting to the database...");
 
         let database_url = match std::env::var("DATABASE_PRIVATE_URL") {
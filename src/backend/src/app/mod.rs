@@ -35,7 +35,7 @@ use crate::{
     custom_login_required,
     middleware::set_cache_control::set_cache_control,
     users::LoginBackend,
-    web::endpoints::{auth, protected, public},
+    web::endpoints::{auth, protected, protected::import::post::fail_interrupted_imports, public},
 };
 
 pub struct App {
@@ -51,6 +51,9 @@ impl App {
     }
 
     pub async fn serve(&self) -> color_eyre::Result<()> {
+        // The jobs of the imports queued before a restart are lost
+        fail_interrupted_imports(&self.db).await?;
+
         // Session layer.
         //
         // This uses `tower-sessions` to establish a layer that will provide the session
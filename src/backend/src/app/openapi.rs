@@ -3,14 +3,15 @@ use utoipa::{
     openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
 };
 
-use crate::web::endpoints::protected::import::post::ImportMode;
+use crate::web::endpoints::protected::import::{diff::DiffFormat, post::ImportMode};
 
 pub const DEFAULT_TAG: &str = "Default";
 pub const AUTH_TAG: &str = "Authentication";
 pub const IMPORT_TAG: &str = "Import";
 pub const DASHBOARD_TAG: &str = "Dashboard";
+pub const STAFF_TAG: &str = "Staff";
 
-// ImportMode specification is a fix for https://github.com/juhaku/utoipa/issues/1165
+// ImportMode and DiffFormat specification is a fix for https://github.com/juhaku/utoipa/issues/1165
 #[derive(OpenApi)]
 #[openapi(
     modifiers(&ApiDocSecurityAddon),
@@ -19,10 +20,12 @@ pub const DASHBOARD_TAG: &str = "Dashboard";
         (name = AUTH_TAG, description = "Authentication related endpoints"),
         (name = IMPORT_TAG, description = "Import related endpoints"),
         (name = DASHBOARD_TAG, description = "Dashboard related endpoints"),
+        (name = STAFF_TAG, description = "Staff related endpoints"),
     ),
     components(
         schemas(
-            ImportMode
+            ImportMode,
+            DiffFormat
         )
     ),
 )]
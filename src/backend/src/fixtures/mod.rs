@@ -1,2 +1,4 @@
 // For future fixture files, please create a new module in the fixtures
 // directory and add it to this mod.rs file.
+
+pub mod school;
@@ -0,0 +1,183 @@
+//! A synthetic school: 30 groups with 30 hours a week each, about 60 teachers
+//! with their DISPO and RECUPERO_ORARIO slots. Everything is derived from the
+//! tables below, so the timetable is the same on every run.
+
+use ahash::AHashSet;
+use color_eyre::Result;
+
+const SURNAMES: [&str; 20] = [
+    "ROSSI", "RUSSO", "FERRARI", "ESPOSITO", "BIANCHI", "ROMANO", "COLOMBO", "RICCI", "MARINO",
+    "GRECO", "BRUNO", "GALLO", "CONTI", "DE LUCA", "MANCINI", "COSTA", "GIORDANO", "RIZZO",
+    "LOMBARDI", "MORETTI",
+];
+
+const NAMES: [&str; 7] = ["MARIO", "GIULIA", "LUCA", "FRANCESCA", "MARCO", "CHIARA", "PAOLO"];
+
+/// Subjects and their weekly hours, 30 in total
+const SUBJECTS: [(&str, usize); 10] = [
+    ("ITALIANO", 4),
+    ("STORIA", 2),
+    ("INGLESE", 3),
+    ("MATEMATICA", 4),
+    ("INFORMATICA", 4),
+    ("SISTEMI E RETI", 3),
+    ("TPSIT", 3),
+    ("SCIENZE MOTORIE", 2),
+    ("RELIGIONE", 1),
+    ("ELETTRONICA", 4),
+];
+
+const DAYS: [&str; 5] = ["LUN", "MAR", "MER", "GIO", "VEN"];
+const TIMES: [&str; 6] = ["8:00", "9:00", "10:00", "11:00", "12:00", "13:00"];
+const SLOTS: usize = DAYS.len() * TIMES.len();
+
+/// Hours a teacher works at most, the pools of each subject are sized on it
+const TEACHER_HOURS: usize = 16;
+
+/// Teachers absent on each of the next weekdays
+const ABSENT_TEACHERS_PER_DAY: usize = 3;
+
+pub struct School {
+    pub teachers: Vec<String>,
+    /// One CSV row per lesson or availability, with the default columns of
+    /// the CSV import
+    rows: Vec<[String; 8]>,
+}
+
+impl School {
+    pub fn generate() -> Self {
+        let groups: Vec<String> = (1..=5)
+            .flat_map(|year| ["A", "B", "C", "D", "E", "F"].map(|s| format!("{year}^{s}")))
+            .collect();
+
+        let mut teacher_names = SURNAMES
+            .iter()
+            .enumerate()
+            .flat_map(|(i, surname)| {
+                (0..3).map(move |j| format!("{surname} {}", NAMES[(i + j * 3) % NAMES.len()]))
+            });
+
+        // Pools of teachers by subject
+        let pools: Vec<Vec<String>> = SUBJECTS
+            .iter()
+            .map(|(_, hours)| {
+                let size = (groups.len() * hours).div_ceil(TEACHER_HOURS);
+                teacher_names.by_ref().take(size).collect()
+            })
+            .collect();
+
+        let mut busy: AHashSet<(String, usize)> = AHashSet::new();
+        let mut rows = Vec::new();
+
+        for (g, group) in groups.iter().enumerate() {
+            let mut free_slots: Vec<usize> = (0..SLOTS).collect();
+            let room = format!("AULA {:02}", g + 1);
+
+            for (s, (subject, hours)) in SUBJECTS.iter().enumerate() {
+                let pool = &pools[s];
+                let teacher = &pool[g % pool.len()];
+
+                for h in 0..*hours {
+                    // Spread the hours over the week, starting from a
+                    // different slot for every group and subject
+                    let start = (g * 7 + s * 5 + h * 6) % SLOTS;
+                    let slot = (0..SLOTS)
+                        .map(|offset| (start + offset) % SLOTS)
+                        .find(|slot| {
+                            free_slots.contains(slot) && !busy.contains(&(teacher.clone(), *slot))
+                        });
+
+                    // A full week can leave an hour without a slot, fine for
+                    // a fixture
+                    let Some(slot) = slot else {
+                        continue;
+                    };
+
+                    free_slots.retain(|free| *free != slot);
+                    busy.insert((teacher.clone(), slot));
+
+                    let room = if *subject == "SCIENZE MOTORIE" {
+                        "PALESTRA".to_owned()
+                    } else {
+                        room.clone()
+                    };
+
+                    rows.push(row(teacher, slot, group, &room, subject, ""));
+                }
+            }
+        }
+
+        let teachers: Vec<String> = pools.into_iter().flatten().collect();
+
+        // Two DISPO and one RECUPERO_ORARIO for each teacher, when free
+        for (t, teacher) in teachers.iter().enumerate() {
+            let mut availabilities = ["DISPO", "DISPO", "RECUPERO_ORARIO"].into_iter();
+
+            for offset in 0..SLOTS {
+                let slot = (t * 11 + offset * 7) % SLOTS;
+
+                if busy.contains(&(teacher.clone(), slot)) {
+                    continue;
+                }
+
+                let Some(availability_type) = availabilities.next() else {
+                    break;
+                };
+
+                busy.insert((teacher.clone(), slot));
+                rows.push(row(teacher, slot, "", "", "", availability_type));
+            }
+        }
+
+        Self { teachers, rows }
+    }
+
+    pub fn to_csv(&self) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+
+        writer.write_record([
+            "teacher",
+            "day",
+            "time",
+            "duration",
+            "group",
+            "room",
+            "subject",
+            "availability_type",
+        ])?;
+
+        for row in &self.rows {
+            writer.write_record(row)?;
+        }
+
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+
+    /// Teachers absent on the n-th weekday from today
+    pub fn absent_teachers(&self, day: usize) -> &[String] {
+        let start = (day * ABSENT_TEACHERS_PER_DAY * 7) % self.teachers.len();
+        let end = (start + ABSENT_TEACHERS_PER_DAY).min(self.teachers.len());
+
+        &self.teachers[start..end]
+    }
+}
+
+fn row(
+    teacher: &str,
+    slot: usize,
+    group: &str,
+    room: &str,
+    subject: &str,
+    availability_type: &str,
+) -> [String; 8] {
+    [
+        teacher.to_owned(),
+        DAYS[slot / TIMES.len()].to_owned(),
+        TIMES[slot % TIMES.len()].to_owned(),
+        "1:00".to_owned(),
+        group.to_owned(),
+        room.to_owned(),
+        subject.to_owned(),
+        availability_type.to_owned(),
+    ]
+}
@@ -5,12 +5,16 @@ pub mod types;
 mod users;
 mod web;
 
+use clap::Parser;
 use color_eyre::Result;
 use dotenvy::dotenv;
 use rustls::crypto::aws_lc_rs;
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::app::App;
+use crate::app::{
+    App,
+    cli::{Args, Command, import::import, seed::seed_lessons},
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -33,27 +37,11 @@ async fn main() -> Result<()> {
         .install_default()
         .expect("Failed to install AWS LC provider");
 
-    let app = App::new().await?;
+    let args = Args::parse();
 
-    #[cfg(debug_assertions)]
-    {
-        use app::cli::Args;
-        use clap::Parser;
-
-        let args = Args::parse();
-
-        match args.command {
-            None => app.serve().await,
-            // For future use with fixtures to import static data
-            _ => Ok(()),
-        }
+    match args.command {
+        None => App::new().await?.serve().await,
+        Some(Command::Import(args)) => import(args).await,
+        Some(Command::SeedLessons(args)) => seed_lessons(args).await,
     }
-
-    // Run the app in production without the CLI
-    #[cfg(not(debug_assertions))]
-    {
-        app.serve().await
-    }
-
-    
 }
@@ -1,9 +1,23 @@
 use axum_serde::macros::{Deserialize, Serialize};
-use chrono::{NaiveTime, TimeDelta};
+use chrono::{Datelike, NaiveDate, NaiveTime, TimeDelta, Weekday};
+use color_eyre::{Report, eyre::eyre};
 use strum::Display;
 use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Deserialize, Serialize, Display)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Deserialize,
+    Serialize,
+    Display,
+    ToSchema,
+)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum IsoDow {
     Mon = 1,
@@ -17,7 +31,40 @@ pub enum IsoDow {
 
 impl IsoDow {
     pub fn iso_dow(&self) -> i16 {
-        self.clone() as i16
+        *self as i16
+    }
+}
+
+impl TryFrom<i16> for IsoDow {
+    type Error = Report;
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Mon),
+            2 => Ok(Self::Tue),
+            3 => Ok(Self::Wed),
+            4 => Ok(Self::Thu),
+            5 => Ok(Self::Fri),
+            6 => Ok(Self::Sat),
+            7 => Ok(Self::Sun),
+            _ => Err(eyre!("Invalid ISO day of week: {}", value)),
+        }
+    }
+}
+
+/// The day of the week of a date, as stored in `lesson.day`. Postgres'
+/// `EXTRACT(DOW ...)` counts from Sunday = 0, use this instead.
+impl From<NaiveDate> for IsoDow {
+    fn from(date: NaiveDate) -> Self {
+        match date.weekday() {
+            Weekday::Mon => Self::Mon,
+            Weekday::Tue => Self::Tue,
+            Weekday::Wed => Self::Wed,
+            Weekday::Thu => Self::Thu,
+            Weekday::Fri => Self::Fri,
+            Weekday::Sat => Self::Sat,
+            Weekday::Sun => Self::Sun,
+        }
     }
 }
 
@@ -27,19 +74,27 @@ pub struct Availability {
     pub day: Option<IsoDow>,
     pub time: Option<NaiveTime>,
     pub availability_type: Option<AvailabilityType>,
+    pub week: Option<Week>,
+    /// The DISPOSIZIONE# room the availability was found in
+    pub room: Option<String>,
+    pub duration: Option<TimeDelta>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct Lesson {
-    pub teacher: Option<String>,
+    pub teachers: Vec<String>,
     pub day: Option<IsoDow>,
     pub time: Option<NaiveTime>,
-    pub room: Option<String>,
-    pub group: Option<String>,
+    pub rooms: Vec<String>,
+    pub groups: Vec<String>,
+    pub subject: Option<String>,
     pub duration: Option<TimeDelta>,
+    pub week: Option<Week>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Display, sqlx::Type, ToSchema)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize, Display, sqlx::Type, ToSchema,
+)]
 #[sqlx(type_name = "availability_type")]
 #[serde(rename_all = "UPPERCASE")]
 pub enum AvailabilityType {
@@ -47,6 +102,16 @@ pub enum AvailabilityType {
     RecoveryHours,
 }
 
+/// Week of an alternating (A/B) timetable
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Display, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "week_label")]
+pub enum Week {
+    A,
+    B,
+}
+
 #[derive(
     Debug, Clone, PartialEq, Default, Deserialize, Serialize, Display, sqlx::Type, ToSchema,
 )]
@@ -59,3 +124,49 @@ pub enum AbsenceStatus {
     ClassCanceled,
     SubstituteFound,
 }
+
+/// Why a teacher is absent
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Display, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "absence_reason")]
+#[serde(rename_all = "camelCase")]
+pub enum AbsenceReason {
+    Illness,
+    TrainingCourse,
+    /// Accompanying students on a school trip
+    SchoolTrip,
+    UnionLeave,
+    PersonalLeave,
+    ExamCommission,
+}
+
+/// Progress of an import processed in the background
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Display, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "import_status")]
+#[serde(rename_all = "camelCase")]
+pub enum ImportStatus {
+    Pending,
+    Running,
+    Failed,
+    Done,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iso_dow_from_date() {
+        // 2026-10-12 is a Monday
+        let monday = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+
+        for (offset, expected) in (1..=7).enumerate() {
+            let date = monday + TimeDelta::days(offset as i64);
+
+            assert_eq!(IsoDow::from(date).iso_dow(), expected, "{date}");
+        }
+    }
+}
@@ -35,10 +35,9 @@ pub async fn delete(
     match sqlx::query!(
         r#"
         DELETE FROM absence ab
-        USING lesson l, teacher t, import i
+        USING teacher t, import i
         WHERE ab.id = $1
-          AND ab.absent_teacher_lesson = l.id
-          AND l.teacher_id = t.id
+          AND ab.absent_teacher = t.id
           AND t.import_id = i.id
           AND i.user_id = $2
         "#,
@@ -1,13 +1,17 @@
 use ahash::AHashMap;
 use axum::{extract::Query, response::IntoResponse};
 use axum_serde::Sonic;
-use chrono::{NaiveDate, NaiveTime};
+use chrono::{Local, NaiveDate, NaiveTime};
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
 use tracing::error;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::{app::openapi::DASHBOARD_TAG, types::AbsenceStatus, users::AuthSession};
+use crate::{
+    app::openapi::DASHBOARD_TAG,
+    types::{AbsenceReason, AbsenceStatus},
+    users::AuthSession,
+};
 
 #[derive(Debug, Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
@@ -29,10 +33,23 @@ struct AbsentClasses {
     substitute_teacher: Option<String>,
     /// Time of the class, e.g., 08:00:00
     time: NaiveTime,
+    /// Rooms of the class, comma separated
     room: Option<String>,
+    /// Groups of the class, comma separated
     group: Option<String>,
+    /// Co-teachers of the class who are not absent
+    present_co_teachers: Vec<String>,
+    /// The class is held anyway by a co-teacher who is not absent
+    co_teacher_present: bool,
+    /// Subject of the class, e.g., INFORMATICA
+    subject: Option<String>,
     /// Current status of the absence
     absent_status: AbsenceStatus,
+    /// The absence over several lessons or days this class is part of, to
+    /// change or cancel it as a whole
+    period_id: Option<i32>,
+    reason: Option<AbsenceReason>,
+    notes: Option<String>,
 }
 
 #[utoipa::path(
@@ -57,34 +74,51 @@ pub async fn get(
         return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
     };
 
+    let date = req.date.unwrap_or_else(|| Local::now().date_naive());
+
     let rows = match sqlx::query!(
         r#"
-        WITH active_import AS (SELECT id
-                       FROM import
-                       WHERE user_id = $2
-                         AND begin_ts <= COALESCE($1, CURRENT_DATE)
-                         AND end_ts >= COALESCE($1, CURRENT_DATE)
-                       ORDER BY import_ts DESC
-                       LIMIT 1)
         SELECT ab.id        AS id,
                t.full_name  AS absent_teacher,
                t.id         AS absent_teacher_id,
                l.time       AS time,
-               r.name       AS room,
-               g.name       AS "group",
+               (SELECT STRING_AGG(r.name, ', ' ORDER BY r.name)
+                FROM lesson_room lr
+                         JOIN room r ON lr.room_id = r.id
+                WHERE lr.lesson_id = l.id) AS room,
+               (SELECT STRING_AGG(g.name, ', ' ORDER BY g.name)
+                FROM lesson_group lg
+                         JOIN "group" g ON lg.group_id = g.id
+                WHERE lg.lesson_id = l.id) AS "group",
+               ARRAY(SELECT ct.full_name
+                     FROM lesson_teacher lt
+                              JOIN teacher ct ON lt.teacher_id = ct.id
+                     WHERE lt.lesson_id = l.id
+                       AND lt.teacher_id <> ab.absent_teacher
+                       AND NOT EXISTS (SELECT 1
+                                       FROM absence cab
+                                       WHERE cab.absent_teacher_lesson = l.id
+                                         AND cab.absent_teacher = lt.teacher_id
+                                         AND cab.absence_date = ab.absence_date)
+                     ORDER BY ct.full_name) AS "present_co_teachers!",
+               s.name       AS "subject?",
                ab.status    AS "absent_status: AbsenceStatus",
-               st.full_name AS substitute_teacher
+               ab.period_id AS period_id,
+               p.reason     AS "reason?: AbsenceReason",
+               p.notes      AS "notes?",
+               st.full_name AS "substitute_teacher?"
         FROM absence ab
                  JOIN lesson l ON ab.absent_teacher_lesson = l.id
-                 JOIN teacher t ON l.teacher_id = t.id
-                 JOIN active_import ON t.import_id = active_import.id
-                 LEFT JOIN room r ON l.room_id = r.id
-                 LEFT JOIN "group" g ON l.group_id = g.id
+                 JOIN teacher t ON ab.absent_teacher = t.id
+                 LEFT JOIN subject s ON l.subject_id = s.id
+                 LEFT JOIN absence_period p ON ab.period_id = p.id
                  LEFT JOIN availability av ON ab.substitute_teacher_availability = av.id
                  LEFT JOIN teacher st ON av.teacher_id = st.id
-        WHERE ab.absence_date = COALESCE($1, CURRENT_DATE);
+        WHERE ab.absence_date = $1
+          AND t.import_id = active_import($2, $1)
+          AND week_applies(l.week, t.import_id, $1);
         "#,
-        req.date,
+        date,
         user.id
     )
     .fetch_all(&auth_session.backend.db)
@@ -112,7 +146,13 @@ pub async fn get(
                 time: row.time,
                 room: row.room,
                 group: row.group,
+                co_teacher_present: !row.present_co_teachers.is_empty(),
+                present_co_teachers: row.present_co_teachers,
+                subject: row.subject,
                 absent_status: row.absent_status,
+                period_id: row.period_id,
+                reason: row.reason,
+                notes: row.notes,
             });
 
             acc
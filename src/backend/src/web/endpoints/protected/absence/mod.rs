@@ -3,8 +3,12 @@ use utoipa_axum::{router::OpenApiRouter, routes};
 mod delete;
 pub mod get;
 mod patch;
+mod period;
 mod post;
 
 pub fn router() -> OpenApiRouter {
-    OpenApiRouter::new().routes(routes!(get::get, post::post, delete::delete, patch::patch))
+    OpenApiRouter::new()
+        .routes(routes!(get::get, post::post, delete::delete, patch::patch))
+        .routes(routes!(post::preview))
+        .routes(routes!(period::put_period, period::delete_period))
 }
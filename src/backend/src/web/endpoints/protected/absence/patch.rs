@@ -19,6 +19,12 @@ pub struct PatchAbsenceRequest {
     substitute_teacher_availability_id: Option<i32>,
 }
 
+enum PatchOutcome {
+    Patched,
+    NotFound,
+    AlreadyBooked,
+}
+
 #[utoipa::path(
     patch,
     path = "/{absence_id}",
@@ -29,6 +35,7 @@ pub struct PatchAbsenceRequest {
         (status = OK, description = "Absence modified"),
         (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
         (status = NOT_FOUND, description = "Absence not found or not accessible"),
+        (status = CONFLICT, description = "The availability is already used for another absence on the same day"),
     ),
     security(("session" = [])),
     tag = DASHBOARD_TAG,
@@ -62,37 +69,90 @@ pub async fn patch(
             .into_response();
     }
 
-    let res = sqlx::query!(
-        r#"
-        UPDATE absence ab
-        SET status = COALESCE($2, ab.status),
-            substitute_teacher_availability = (
-                SELECT av.id
-                FROM availability av
-                JOIN teacher t2 ON av.teacher_id = t2.id
-                JOIN import i2 ON t2.import_id = i2.id
-                WHERE av.id = $3
-                    AND i2.user_id = $4
-            )
-        FROM lesson l, teacher t, import i
-        WHERE ab.id = $1
-          AND ab.absent_teacher_lesson = l.id
-          AND l.teacher_id = t.id
-          AND t.import_id = i.id
-          AND i.user_id = $5
-        "#,
-        path.absence_id,
-        req.status as AbsenceStatus,
-        req.substitute_teacher_availability_id,
-        user.id,
-        user.id
-    )
-    .execute(&auth_session.backend.db)
+    let res: Result<PatchOutcome, sqlx::Error> = async {
+        let mut txn = auth_session.backend.db.begin().await?;
+
+        let done = sqlx::query!(
+            r#"
+            UPDATE absence ab
+            SET status = COALESCE($2, ab.status),
+                substitute_teacher_availability = (
+                    SELECT av.id
+                    FROM availability av
+                    JOIN teacher t2 ON av.teacher_id = t2.id
+                    JOIN import i2 ON t2.import_id = i2.id
+                    WHERE av.id = $3
+                        AND i2.user_id = $4
+                        AND i2.id = active_import($4, ab.absence_date)
+                )
+            FROM teacher t, import i
+            WHERE ab.id = $1
+              AND ab.absent_teacher = t.id
+              AND t.import_id = i.id
+              AND i.user_id = $5
+            "#,
+            path.absence_id,
+            req.status as AbsenceStatus,
+            req.substitute_teacher_availability_id,
+            user.id,
+            user.id
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        if done.rows_affected() == 0 {
+            return Ok(PatchOutcome::NotFound);
+        }
+
+        // The update only keeps the caller's availabilities. Locking the one
+        // set, in its own statement, makes concurrent requests see each other
+        sqlx::query!(
+            r#"
+            SELECT av.id
+            FROM availability av
+                     JOIN absence ab ON ab.substitute_teacher_availability = av.id
+            WHERE ab.id = $1
+                FOR UPDATE OF av
+            "#,
+            path.absence_id,
+        )
+        .fetch_optional(&mut *txn)
+        .await?;
+
+        let booked = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (SELECT 1
+                           FROM absence other
+                           WHERE other.absence_date = ab.absence_date
+                             AND other.id <> ab.id
+                             AND other.substitute_teacher_availability =
+                                 ab.substitute_teacher_availability) AS "booked!"
+            FROM absence ab
+            WHERE ab.id = $1
+            "#,
+            path.absence_id,
+        )
+        .fetch_one(&mut *txn)
+        .await?;
+
+        if booked {
+            return Ok(PatchOutcome::AlreadyBooked);
+        }
+
+        txn.commit().await?;
+
+        Ok(PatchOutcome::Patched)
+    }
     .await;
 
     match res {
-        Ok(done) if done.rows_affected() >= 1 => StatusCode::OK.into_response(),
-        Ok(_) => (StatusCode::BAD_REQUEST, "Bad Request").into_response(),
+        Ok(PatchOutcome::Patched) => StatusCode::OK.into_response(),
+        Ok(PatchOutcome::NotFound) => (StatusCode::NOT_FOUND, "Absence not found").into_response(),
+        Ok(PatchOutcome::AlreadyBooked) => (
+            StatusCode::CONFLICT,
+            "The substitute already covers another class with this availability on that day",
+        )
+            .into_response(),
         Err(e) => {
             error!("Failed to modify absence: {}", e);
             (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
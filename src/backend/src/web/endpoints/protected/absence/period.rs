@@ -0,0 +1,410 @@
+use axum::{extract::Path, response::IntoResponse};
+use axum_serde::Sonic;
+use chrono::{NaiveDate, NaiveTime};
+use color_eyre::Result;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    app::openapi::DASHBOARD_TAG,
+    types::{AbsenceReason, IsoDow},
+    users::AuthSession,
+};
+
+/// Days an absence can last, about a school year
+const MAX_PERIOD_DAYS: i64 = 366;
+
+/// Days and hours of an absence
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AbsencePeriodSpec {
+    /// First day of the absence
+    pub(super) begin_date: NaiveDate,
+    /// Last day of the absence, included
+    pub(super) end_date: NaiveDate,
+    /// Start of the absence on each day, e.g., 08:00:00. Leave both times
+    /// empty for whole days.
+    pub(super) begin_time: Option<NaiveTime>,
+    /// End of the absence on each day, e.g., 10:00:00, excluded
+    pub(super) end_time: Option<NaiveTime>,
+    pub(super) reason: Option<AbsenceReason>,
+    /// Free text, e.g. the protocol number of the leave
+    pub(super) notes: Option<String>,
+}
+
+impl AbsencePeriodSpec {
+    pub(super) fn validate(&self) -> Result<(), &'static str> {
+        if self.begin_date > self.end_date {
+            return Err("begin_date must be before end_date");
+        }
+        if (self.end_date - self.begin_date).num_days() >= MAX_PERIOD_DAYS {
+            return Err("An absence can't last more than a year");
+        }
+        match (self.begin_time, self.end_time) {
+            (Some(begin), Some(end)) if begin >= end => Err("begin_time must be before end_time"),
+            (Some(_), None) | (None, Some(_)) => {
+                Err("begin_time and end_time must be given together")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AbsencePeriod {
+    id: i32,
+    begin_date: NaiveDate,
+    end_date: NaiveDate,
+    begin_time: Option<NaiveTime>,
+    end_time: Option<NaiveTime>,
+    reason: Option<AbsenceReason>,
+    notes: Option<String>,
+    /// Lessons the teacher misses in the period
+    absences: i64,
+}
+
+impl AbsencePeriod {
+    pub(super) async fn load(conn: &mut PgConnection, period_id: i32) -> Result<Self> {
+        let period = sqlx::query_as!(
+            AbsencePeriod,
+            r#"
+            SELECT p.id,
+                   p.begin_date,
+                   p.end_date,
+                   p.begin_time,
+                   p.end_time,
+                   p.reason AS "reason: AbsenceReason",
+                   p.notes,
+                   (SELECT COUNT(*) FROM absence ab WHERE ab.period_id = p.id) AS "absences!"
+            FROM absence_period p
+            WHERE p.id = $1
+            "#,
+            period_id,
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(period)
+    }
+}
+
+/// Creates the absences of the period: one for every lesson of the teacher,
+/// on every day of the period, in the import active on that day. A lesson is
+/// missed if any part of it, from its time for its duration, falls within the
+/// hours of the period. Lessons already marked as absent are left as they are.
+pub(super) async fn expand_period(conn: &mut PgConnection, period_id: i32) -> Result<u64> {
+    let period = sqlx::query!(
+        r#"
+        SELECT begin_date, end_date FROM absence_period WHERE id = $1
+        "#,
+        period_id,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let (dates, days): (Vec<NaiveDate>, Vec<i16>) = period
+        .begin_date
+        .iter_days()
+        .take_while(|date| *date <= period.end_date)
+        .map(|date| (date, IsoDow::from(date).iso_dow()))
+        .unzip();
+
+    let created = sqlx::query!(
+        r#"
+        INSERT INTO absence (absent_teacher_lesson, absent_teacher, absence_date, period_id)
+        SELECT l.id, t.id, d.date, p.id
+        FROM absence_period p
+                 CROSS JOIN UNNEST($2::date[], $3::smallint[]) AS d(date, day)
+                 JOIN teacher t ON t.staff_id = p.staff_id
+            AND t.import_id = active_import(p.user_id, d.date)
+                 JOIN lesson_teacher lt ON lt.teacher_id = t.id
+                 JOIN lesson l ON lt.lesson_id = l.id
+        WHERE p.id = $1
+          AND l.day = d.day
+          AND (p.begin_time IS NULL
+            OR (l.time::time < p.end_time AND l.time::time + l.duration > p.begin_time))
+          AND week_applies(l.week, t.import_id, d.date)
+        ON CONFLICT (absent_teacher_lesson, absent_teacher, absence_date) DO NOTHING
+        "#,
+        period_id,
+        &dates,
+        &days,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(created.rows_affected())
+}
+
+/// Deletes the absences of the period outside of its days and hours, e.g.
+/// after it was shortened.
+async fn prune_period(conn: &mut PgConnection, period_id: i32) -> Result<()> {
+    sqlx::query!(
+        r#"
+        DELETE
+        FROM absence ab
+            USING absence_period p, lesson l
+        WHERE ab.period_id = p.id
+          AND p.id = $1
+          AND l.id = ab.absent_teacher_lesson
+          AND (ab.absence_date NOT BETWEEN p.begin_date AND p.end_date
+            OR (p.begin_time IS NOT NULL
+                AND NOT (l.time::time < p.end_time AND l.time::time + l.duration > p.begin_time)))
+        "#,
+        period_id,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AbsencePeriodPathParams {
+    period_id: i32,
+}
+
+#[utoipa::path(
+    put,
+    path = "/period/{period_id}",
+    summary = "Change an absence",
+    description = "The absences of the lessons no longer in the period are deleted, those of the \
+                   new days are added. The others keep their status and substitute.",
+    params(AbsencePeriodPathParams),
+    request_body = AbsencePeriodSpec,
+    responses(
+        (status = OK, description = "The absence period", body = AbsencePeriod),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = BAD_REQUEST, description = "Invalid input"),
+        (status = NOT_FOUND, description = "Absence not found or not accessible"),
+    ),
+    security(("session" = [])),
+    tag = DASHBOARD_TAG,
+)]
+pub async fn put_period(
+    auth_session: AuthSession,
+    Path(path): Path<AbsencePeriodPathParams>,
+    Sonic(req): Sonic<AbsencePeriodSpec>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    if let Err(message) = req.validate() {
+        return (StatusCode::BAD_REQUEST, message).into_response();
+    }
+
+    let res: Result<Option<AbsencePeriod>> = async {
+        let mut txn = auth_session.backend.db.begin().await?;
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE absence_period
+            SET begin_date = $3,
+                end_date   = $4,
+                begin_time = $5,
+                end_time   = $6,
+                reason     = $7,
+                notes      = $8
+            WHERE id = $1
+              AND user_id = $2
+            "#,
+            path.period_id,
+            user.id,
+            req.begin_date,
+            req.end_date,
+            req.begin_time,
+            req.end_time,
+            req.reason as Option<AbsenceReason>,
+            req.notes,
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        prune_period(&mut txn, path.period_id).await?;
+        expand_period(&mut txn, path.period_id).await?;
+
+        let period = AbsencePeriod::load(&mut txn, path.period_id).await?;
+
+        txn.commit().await?;
+
+        Ok(Some(period))
+    }
+    .await;
+
+    match res {
+        Ok(Some(period)) => Sonic(period).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Absence not found").into_response(),
+        Err(e) => {
+            error!("Failed to modify the absence period: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/period/{period_id}",
+    summary = "Cancel an absence",
+    description = "Deletes the absences of every lesson in the period.",
+    params(AbsencePeriodPathParams),
+    responses(
+        (status = OK, description = "Deleted absence"),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Absence not found or not accessible"),
+    ),
+    security(("session" = [])),
+    tag = DASHBOARD_TAG,
+)]
+pub async fn delete_period(
+    auth_session: AuthSession,
+    Path(path): Path<AbsencePeriodPathParams>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    match sqlx::query!(
+        r#"
+        DELETE FROM absence_period
+        WHERE id = $1
+          AND user_id = $2
+        "#,
+        path.period_id,
+        user.id,
+    )
+    .execute(&auth_session.backend.db)
+    .await
+    {
+        Ok(done) if done.rows_affected() >= 1 => StatusCode::OK.into_response(),
+        Ok(_) => (StatusCode::NOT_FOUND, "Absence not found").into_response(),
+        Err(e) => {
+            error!("Failed to delete the absence period: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::web::endpoints::protected::import::post::{
+        ImportFileMeta, ImportMode, import_local_file,
+    };
+
+    // ROSSI and BIANCHI share the lesson at 8:00, ROSSI alone has the one at
+    // 11:00, every Monday
+    const SCHEDULE: &str = r#"<dataroot>
+        <LESSON>
+            <DURATION>2:00</DURATION><SUBJECT>INFORMATICA</SUBJECT>
+            <TEACHER>ROSSI MARIO</TEACHER><TEACHER>BIANCHI LUCA</TEACHER>
+            <GROUP>5^A-IA</GROUP><ROOM>07-TW</ROOM>
+            <DAY>LUN</DAY><TIME>8:00</TIME>
+        </LESSON>
+        <LESSON>
+            <DURATION>1:00</DURATION><SUBJECT>SISTEMI</SUBJECT>
+            <TEACHER>ROSSI MARIO</TEACHER><GROUP>5^A-IA</GROUP><ROOM>07-TW</ROOM>
+            <DAY>LUN</DAY><TIME>11:00</TIME>
+        </LESSON>
+    </dataroot>"#;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 9, day).unwrap()
+    }
+
+    async fn count(db: &PgPool, period_id: i32) -> Result<i64> {
+        Ok(sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM absence WHERE period_id = $1
+            "#,
+            period_id,
+        )
+        .fetch_one(db)
+        .await?)
+    }
+
+    #[sqlx::test]
+    async fn expand_and_prune_co_taught_lessons(db: PgPool) -> Result<()> {
+        let user_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO "user" (username, password) VALUES ('test', '') RETURNING id
+            "#
+        )
+        .fetch_one(&db)
+        .await?;
+
+        // Two weeks from Monday 2026-09-14
+        let meta = ImportFileMeta::new(
+            "orario.xml".to_owned(),
+            ImportMode::Write,
+            date(14).and_time(NaiveTime::MIN),
+            date(27).and_hms_opt(23, 59, 59).unwrap(),
+            None,
+        );
+        let import_id = import_local_file(&db, meta, SCHEDULE.as_bytes(), user_id)
+            .await?
+            .import_id()
+            .unwrap();
+
+        let period = async |teacher: &str, begin: u32, end: u32| -> Result<i32> {
+            Ok(sqlx::query_scalar!(
+                r#"
+                INSERT INTO absence_period (user_id, staff_id, begin_date, end_date)
+                SELECT $1, t.staff_id, $2, $3
+                FROM teacher t
+                WHERE t.import_id = $4
+                  AND t.full_name = $5
+                RETURNING id
+                "#,
+                user_id,
+                date(begin),
+                date(end),
+                import_id,
+                teacher,
+            )
+            .fetch_one(&db)
+            .await?)
+        };
+
+        let rossi = period("ROSSI MARIO", 14, 21).await?;
+        let bianchi = period("BIANCHI LUCA", 14, 14).await?;
+
+        let mut conn = db.acquire().await?;
+
+        // Both lessons on both Mondays
+        assert_eq!(expand_period(&mut conn, rossi).await?, 4);
+        // The co-taught lesson is missed by BIANCHI too
+        assert_eq!(expand_period(&mut conn, bianchi).await?, 1);
+        // Lessons already marked as absent are left as they are
+        assert_eq!(expand_period(&mut conn, rossi).await?, 0);
+
+        // Only the first hour of the first Monday
+        sqlx::query!(
+            r#"
+            UPDATE absence_period
+            SET end_date   = begin_date,
+                begin_time = '08:00',
+                end_time   = '09:00'
+            WHERE id = $1
+            "#,
+            rossi,
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        prune_period(&mut conn, rossi).await?;
+
+        assert_eq!(count(&db, rossi).await?, 1);
+        assert_eq!(count(&db, bianchi).await?, 1);
+
+        Ok(())
+    }
+}
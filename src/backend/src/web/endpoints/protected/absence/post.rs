@@ -1,33 +1,125 @@
 use axum::response::IntoResponse;
 use axum_serde::Sonic;
-use chrono::{NaiveDate, NaiveTime};
+use chrono::{Local, NaiveDate, NaiveTime};
+use color_eyre::Result;
 use http::StatusCode;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
 use tracing::error;
 use utoipa::ToSchema;
 
-use crate::{app::openapi::DASHBOARD_TAG, users::AuthSession};
+use crate::{
+    app::openapi::DASHBOARD_TAG,
+    types::AbsenceReason,
+    users::AuthSession,
+    web::endpoints::protected::absence::period::{AbsencePeriod, AbsencePeriodSpec, expand_period},
+};
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct AddAbsenceRequest {
-    /// The date of the absence. If not provided, defaults to today.
+    /// The date of the absence, or its first day. If not provided, defaults to today.
     date: Option<NaiveDate>,
+    /// The last day of the absence, included. If not provided, the absence lasts one day.
+    end_date: Option<NaiveDate>,
     absent_teacher_id: i32,
-    /// Start of the absence. e.g., 08:00:00
-    begin_time: NaiveTime,
-    /// End of the absence. e.g., 10:00:00
-    end_time: NaiveTime,
+    /// Start of the absence on each day. e.g., 08:00:00. Leave both times
+    /// empty for whole days.
+    begin_time: Option<NaiveTime>,
+    /// End of the absence on each day, excluded. e.g., 10:00:00
+    end_time: Option<NaiveTime>,
+    reason: Option<AbsenceReason>,
+    /// Free text, e.g. the protocol number of the leave
+    notes: Option<String>,
+}
+
+impl AddAbsenceRequest {
+    fn spec(&self) -> AbsencePeriodSpec {
+        let begin_date = self.date.unwrap_or_else(|| Local::now().date_naive());
+
+        AbsencePeriodSpec {
+            begin_date,
+            end_date: self.end_date.unwrap_or(begin_date),
+            begin_time: self.begin_time,
+            end_time: self.end_time,
+            reason: self.reason,
+            notes: self.notes.clone(),
+        }
+    }
+}
+
+/// A lesson the teacher would miss
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AffectedLesson {
+    date: NaiveDate,
+    time: NaiveTime,
+    duration_minutes: i32,
+    subject: Option<String>,
+    groups: Vec<String>,
+    rooms: Vec<String>,
+}
+
+enum PostOutcome {
+    Added(AbsencePeriod),
+    TeacherNotFound,
+    NoLessons,
+}
+
+/// Creates the period of the absence and its absences, returns the id of the
+/// period and the number of absences. `None` if the teacher is not found.
+async fn create_period(
+    conn: &mut PgConnection,
+    user_id: i32,
+    absent_teacher_id: i32,
+    spec: &AbsencePeriodSpec,
+) -> Result<Option<(i32, u64)>> {
+    // The teacher is given as one of an import, the period follows them in
+    // the other imports
+    let period_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO absence_period (user_id, staff_id, begin_date, end_date, begin_time, end_time,
+                                    reason, notes)
+        SELECT i.user_id, t.staff_id, $3, $4, $5, $6, $7, $8
+        FROM teacher t
+                 JOIN import i ON t.import_id = i.id
+        WHERE t.id = $1
+          AND i.user_id = $2
+          AND t.staff_id IS NOT NULL
+        RETURNING id
+        "#,
+        absent_teacher_id,
+        user_id,
+        spec.begin_date,
+        spec.end_date,
+        spec.begin_time,
+        spec.end_time,
+        spec.reason as Option<AbsenceReason>,
+        spec.notes,
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(period_id) = period_id else {
+        return Ok(None);
+    };
+
+    let absences = expand_period(conn, period_id).await?;
+
+    Ok(Some((period_id, absences)))
 }
 
 #[utoipa::path(
     post,
     path = "/",
     summary = "Add an absence",
+    description = "Marks as absent every lesson of the teacher overlapping the given days and \
+                   hours, each day in the import active on it. The absence can then be changed \
+                   or cancelled as a whole with `/absence/period/{period_id}`.",
     request_body = AddAbsenceRequest,
     responses(
-        (status = OK, description = "Absence added"),
+        (status = OK, description = "Absence added", body = AbsencePeriod),
         (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
-        (status = BAD_REQUEST, description = "Invalid input")
+        (status = BAD_REQUEST, description = "Invalid input, or no lessons of the teacher in the period"),
+        (status = NOT_FOUND, description = "Teacher not found or not accessible"),
     ),
     security(("session" = [])),
     tag = DASHBOARD_TAG,
@@ -40,41 +132,131 @@ pub async fn post(
         return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
     };
 
-    if req.begin_time > req.end_time {
-        return (StatusCode::BAD_REQUEST, "begin_ts must be before end_ts").into_response();
+    let spec = req.spec();
+
+    if let Err(message) = spec.validate() {
+        return (StatusCode::BAD_REQUEST, message).into_response();
     }
 
-    let res = sqlx::query!(
-        r#"
-        WITH lessons AS (
-          SELECT le.id
-          FROM lesson le
-          JOIN teacher t ON le.teacher_id = t.id
-          JOIN import i ON t.import_id = i.id
-          WHERE le.teacher_id = $1
-            AND le.day = EXTRACT(DOW FROM COALESCE($2, CURRENT_DATE)::date)::int
-            AND le.time::time BETWEEN ($3::time) AND ($4::time)
-            AND i.user_id = $5
-        )
-        INSERT INTO absence (absent_teacher_lesson, absence_date)
-        SELECT l.id, COALESCE($2, CURRENT_DATE)::date
-        FROM lessons l;
-        "#,
-        req.absent_teacher_id,
-        req.date,
-        req.begin_time,
-        req.end_time,
-        user.id
-    )
-    .execute(&auth_session.backend.db)
+    let res: Result<PostOutcome> = async {
+        let mut txn = auth_session.backend.db.begin().await?;
+
+        let Some((period_id, absences)) =
+            create_period(&mut txn, user.id, req.absent_teacher_id, &spec).await?
+        else {
+            return Ok(PostOutcome::TeacherNotFound);
+        };
+
+        if absences == 0 {
+            return Ok(PostOutcome::NoLessons);
+        }
+
+        let period = AbsencePeriod::load(&mut txn, period_id).await?;
+
+        txn.commit().await?;
+
+        Ok(PostOutcome::Added(period))
+    }
     .await;
 
     match res {
-        Ok(done) if done.rows_affected() >= 1 => StatusCode::OK.into_response(),
-        Ok(_) => (StatusCode::INTERNAL_SERVER_ERROR, "No rows affected").into_response(),
+        Ok(PostOutcome::Added(period)) => Sonic(period).into_response(),
+        Ok(PostOutcome::TeacherNotFound) => {
+            (StatusCode::NOT_FOUND, "Teacher not found").into_response()
+        }
+        Ok(PostOutcome::NoLessons) => (
+            StatusCode::BAD_REQUEST,
+            "The teacher has no lessons in the given days and hours",
+        )
+            .into_response(),
         Err(e) => {
-            error!("Failed to add absence: {}", e);
+            error!("Failed to add absence: {:?}", e);
             (StatusCode::INTERNAL_SERVER_ERROR, "Failed to add absence").into_response()
         }
     }
 }
+
+#[utoipa::path(
+    post,
+    path = "/preview",
+    summary = "Preview an absence",
+    description = "The lessons `POST /absence` would mark as absent, without adding the absence. \
+                   Lessons already marked as absent are not listed.",
+    request_body = AddAbsenceRequest,
+    responses(
+        (status = OK, description = "The lessons the teacher would miss", body = Vec<AffectedLesson>),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = BAD_REQUEST, description = "Invalid input"),
+        (status = NOT_FOUND, description = "Teacher not found or not accessible"),
+    ),
+    security(("session" = [])),
+    tag = DASHBOARD_TAG,
+)]
+pub async fn preview(
+    auth_session: AuthSession,
+    Sonic(req): Sonic<AddAbsenceRequest>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let spec = req.spec();
+
+    if let Err(message) = spec.validate() {
+        return (StatusCode::BAD_REQUEST, message).into_response();
+    }
+
+    // The absence is added like `post` does, then rolled back
+    let res: Result<Option<Vec<AffectedLesson>>> = async {
+        let mut txn = auth_session.backend.db.begin().await?;
+
+        let Some((period_id, _)) =
+            create_period(&mut txn, user.id, req.absent_teacher_id, &spec).await?
+        else {
+            return Ok(None);
+        };
+
+        let lessons = sqlx::query_as!(
+            AffectedLesson,
+            r#"
+            SELECT ab.absence_date                                AS date,
+                   l.time::time                                   AS "time!",
+                   (EXTRACT(EPOCH FROM l.duration) / 60)::integer AS "duration_minutes!",
+                   s.name                                         AS "subject?",
+                   ARRAY(SELECT g.name
+                         FROM lesson_group lg
+                                  JOIN "group" g ON lg.group_id = g.id
+                         WHERE lg.lesson_id = l.id
+                         ORDER BY g.name)                         AS "groups!",
+                   ARRAY(SELECT r.name
+                         FROM lesson_room lr
+                                  JOIN room r ON lr.room_id = r.id
+                         WHERE lr.lesson_id = l.id
+                           AND r.name IS NOT NULL
+                         ORDER BY r.name)                         AS "rooms!"
+            FROM absence ab
+                     JOIN lesson l ON ab.absent_teacher_lesson = l.id
+                     LEFT JOIN subject s ON l.subject_id = s.id
+            WHERE ab.period_id = $1
+            ORDER BY ab.absence_date, l.time
+            "#,
+            period_id,
+        )
+        .fetch_all(&mut *txn)
+        .await?;
+
+        txn.rollback().await?;
+
+        Ok(Some(lessons))
+    }
+    .await;
+
+    match res {
+        Ok(Some(lessons)) => Sonic(lessons).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Teacher not found").into_response(),
+        Err(e) => {
+            error!("Failed to preview the absence: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
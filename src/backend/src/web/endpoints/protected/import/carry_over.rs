@@ -0,0 +1,242 @@
+use ahash::AHashSet;
+use axum::{extract::Path, response::IntoResponse};
+use axum_serde::Sonic;
+use chrono::{Local, NaiveDate, NaiveTime};
+use color_eyre::Result;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{app::openapi::IMPORT_TAG, types::IsoDow, users::AuthSession};
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct CarryOverReport {
+    /// Number of absences moved to the lessons of the import
+    carried_over: usize,
+    /// Absences moved whose substitute has no matching availability in the
+    /// import, they are uncovered again
+    substitutes_lost: Vec<CarriedAbsence>,
+    /// Absences without an equivalent lesson in the import, left untouched
+    unmatched: Vec<CarriedAbsence>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CarriedAbsence {
+    absence_id: i32,
+    date: NaiveDate,
+    teacher: String,
+    day: IsoDow,
+    time: NaiveTime,
+}
+
+/// Moves the future absences registered on older imports of the user to the
+/// equivalent lessons of `import_id`, matching them by teacher name, day and
+/// time. Only absences within the dates of the import are considered.
+pub async fn carry_over_absences(
+    conn: &mut PgConnection,
+    import_id: i32,
+    user_id: i32,
+) -> Result<CarryOverReport> {
+    let today = Local::now().date_naive();
+
+    // Every candidate with its lesson and the availability of its substitute
+    // in the import, if any
+    let candidates = sqlx::query!(
+        r#"
+        SELECT ab.id           AS id,
+               ab.absence_date AS absence_date,
+               t.full_name     AS teacher,
+               l.day::smallint AS "day!",
+               l.time::time    AS "time!",
+               st.full_name    AS "substitute?",
+               m.lesson_id     AS "lesson_id?",
+               m.teacher_id    AS "teacher_id?",
+               sav.id          AS "substitute_availability?"
+        FROM absence ab
+                 JOIN lesson l ON ab.absent_teacher_lesson = l.id
+                 JOIN teacher t ON ab.absent_teacher = t.id
+                 JOIN import i ON t.import_id = i.id
+                 JOIN import target ON target.id = $2
+                 LEFT JOIN availability av ON ab.substitute_teacher_availability = av.id
+                 LEFT JOIN teacher st ON av.teacher_id = st.id
+                 LEFT JOIN LATERAL (SELECT nl.id AS lesson_id, nt.id AS teacher_id
+                                    FROM lesson nl
+                                             JOIN lesson_teacher nlt ON nlt.lesson_id = nl.id
+                                             JOIN teacher nt ON nlt.teacher_id = nt.id
+                                    WHERE nl.import_id = target.id
+                                      AND nt.full_name = t.full_name
+                                      AND nl.day = l.day
+                                      AND nl.time::time = l.time::time
+                                      AND week_applies(nl.week, target.id, ab.absence_date)
+                                      AND NOT EXISTS (SELECT 1
+                                                      FROM absence e
+                                                      WHERE e.absent_teacher_lesson = nl.id
+                                                        AND e.absent_teacher = nt.id
+                                                        AND e.absence_date = ab.absence_date)
+                                    ORDER BY nl.id
+                                    LIMIT 1) m ON TRUE
+                 LEFT JOIN LATERAL (SELECT nav.id
+                                    FROM availability nav
+                                             JOIN teacher nst ON nav.teacher_id = nst.id
+                                    WHERE nst.import_id = target.id
+                                      AND nst.full_name = st.full_name
+                                      AND nav.day = l.day
+                                      AND nav.time::time = l.time::time
+                                      AND week_applies(nav.week, target.id, ab.absence_date)
+                                    ORDER BY nav.id
+                                    LIMIT 1) sav ON TRUE
+        WHERE i.user_id = $1
+          AND target.user_id = $1
+          AND i.id <> target.id
+          AND i.import_ts <= target.import_ts
+          AND ab.absence_date >= $3
+          AND ab.absence_date BETWEEN target.begin_ts::date AND target.end_ts::date
+        ORDER BY ab.absence_date, t.full_name, l.time
+        "#,
+        user_id,
+        import_id,
+        today,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut report = CarryOverReport::default();
+
+    let mut absence_ids = Vec::with_capacity(candidates.len());
+    let mut lesson_ids = Vec::with_capacity(candidates.len());
+    let mut teacher_ids = Vec::with_capacity(candidates.len());
+    let mut availability_ids = Vec::with_capacity(candidates.len());
+
+    // Absences of the same teacher and lesson from several older imports
+    // can't all move to the one lesson, the first one does
+    let mut taken = AHashSet::new();
+
+    for absence in candidates {
+        let carried = CarriedAbsence {
+            absence_id: absence.id,
+            date: absence.absence_date,
+            teacher: absence.teacher,
+            day: absence.day.try_into()?,
+            time: absence.time,
+        };
+
+        let (Some(lesson_id), Some(teacher_id)) = (absence.lesson_id, absence.teacher_id) else {
+            report.unmatched.push(carried);
+            continue;
+        };
+
+        if !taken.insert((lesson_id, teacher_id, carried.date)) {
+            report.unmatched.push(carried);
+            continue;
+        }
+
+        absence_ids.push(carried.absence_id);
+        lesson_ids.push(lesson_id);
+        teacher_ids.push(teacher_id);
+        availability_ids.push(absence.substitute_availability);
+
+        report.carried_over += 1;
+
+        if absence.substitute.is_some() && absence.substitute_availability.is_none() {
+            report.substitutes_lost.push(carried);
+        }
+    }
+
+    if absence_ids.is_empty() {
+        return Ok(report);
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE absence ab
+        SET absent_teacher_lesson           = c.lesson_id,
+            absent_teacher                  = c.teacher_id,
+            substitute_teacher_availability = c.availability_id,
+            status                          = CASE
+                                                  WHEN c.availability_id IS NULL
+                                                      AND ab.status = 'SubstituteFound'
+                                                      THEN 'Uncovered'
+                                                  ELSE ab.status
+                END
+        FROM UNNEST($1::integer[], $2::integer[], $3::integer[], $4::integer[])
+                 AS c(id, lesson_id, teacher_id, availability_id)
+        WHERE ab.id = c.id
+        "#,
+    )
+    .bind(absence_ids)
+    .bind(lesson_ids)
+    .bind(teacher_ids)
+    .bind(availability_ids)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(report)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CarryOverPathParams {
+    import_id: i32,
+}
+
+#[utoipa::path(
+    post,
+    path = "/{import_id}/carry_over",
+    summary = "Carry absences over to an import",
+    description = "Moves the future absences registered on older imports to the equivalent \
+                   lessons of this import, matching them by teacher name, day and time. \
+                   Imports already do this when they are created.",
+    params(CarryOverPathParams),
+    responses(
+        (status = OK, description = "Absences carried over and the ones that could not be matched", body = CarryOverReport),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Import not found or not accessible"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = IMPORT_TAG,
+)]
+pub async fn carry_over(
+    auth_session: AuthSession,
+    Path(path): Path<CarryOverPathParams>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let res: Result<Option<CarryOverReport>> = async {
+        let mut txn = auth_session.backend.db.begin().await?;
+
+        let import = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM import WHERE id = $1 AND user_id = $2
+            "#,
+            path.import_id,
+            user.id,
+        )
+        .fetch_optional(&mut *txn)
+        .await?;
+
+        if import.is_none() {
+            return Ok(None);
+        }
+
+        let report = carry_over_absences(&mut txn, path.import_id, user.id).await?;
+
+        txn.commit().await?;
+
+        Ok(Some(report))
+    }
+    .await;
+
+    match res {
+        Ok(Some(report)) => Sonic(report).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Import not found").into_response(),
+        Err(e) => {
+            error!("Error when carrying absences over: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
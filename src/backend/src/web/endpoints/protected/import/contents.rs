@@ -0,0 +1,192 @@
+use chrono::NaiveTime;
+use color_eyre::Result;
+use serde::Serialize;
+use sqlx::PgConnection;
+use utoipa::ToSchema;
+
+use crate::types::{AvailabilityType, IsoDow, Week};
+
+/// Everything an import contains, with names in place of ids.
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+pub struct ImportContents {
+    pub teachers: Vec<String>,
+    pub rooms: Vec<String>,
+    pub groups: Vec<String>,
+    pub subjects: Vec<String>,
+    pub lessons: Vec<LessonEntry>,
+    pub availabilities: Vec<AvailabilityEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportCounts {
+    pub teachers: usize,
+    pub rooms: usize,
+    pub groups: usize,
+    pub subjects: usize,
+    pub lessons: usize,
+    pub availabilities: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, ToSchema)]
+pub struct LessonEntry {
+    pub teachers: Vec<String>,
+    pub day: IsoDow,
+    pub time: NaiveTime,
+    pub duration_minutes: i32,
+    pub rooms: Vec<String>,
+    pub groups: Vec<String>,
+    pub subject: Option<String>,
+    pub week: Option<Week>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, ToSchema)]
+pub struct AvailabilityEntry {
+    pub teacher: String,
+    pub day: IsoDow,
+    pub time: NaiveTime,
+    pub availability_type: AvailabilityType,
+    pub week: Option<Week>,
+    /// The DISPOSIZIONE# room, unknown for imports older than the column
+    pub room: Option<String>,
+    pub duration_minutes: i32,
+}
+
+impl ImportContents {
+    pub async fn load(conn: &mut PgConnection, import_id: i32) -> Result<Self> {
+        let teachers = sqlx::query_scalar!(
+            r#"
+            SELECT full_name FROM teacher WHERE import_id = $1 ORDER BY full_name
+            "#,
+            import_id
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let rooms = sqlx::query_scalar!(
+            r#"
+            SELECT name AS "name!" FROM room WHERE import_id = $1 AND name IS NOT NULL ORDER BY name
+            "#,
+            import_id
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let groups = sqlx::query_scalar!(
+            r#"
+            SELECT name FROM "group" WHERE import_id = $1 ORDER BY name
+            "#,
+            import_id
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let subjects = sqlx::query_scalar!(
+            r#"
+            SELECT name FROM subject WHERE import_id = $1 ORDER BY name
+            "#,
+            import_id
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let lessons = sqlx::query!(
+            r#"
+            SELECT l.day::smallint                                   AS "day!",
+                   l.time::time                                      AS "time!",
+                   (EXTRACT(EPOCH FROM l.duration) / 60)::integer    AS "duration_minutes!",
+                   l.week                                            AS "week: Week",
+                   s.name                                            AS "subject?",
+                   ARRAY(SELECT t.full_name
+                         FROM lesson_teacher lt
+                                  JOIN teacher t ON lt.teacher_id = t.id
+                         WHERE lt.lesson_id = l.id
+                         ORDER BY t.full_name)                       AS "teachers!",
+                   ARRAY(SELECT r.name
+                         FROM lesson_room lr
+                                  JOIN room r ON lr.room_id = r.id
+                         WHERE lr.lesson_id = l.id
+                           AND r.name IS NOT NULL
+                         ORDER BY r.name)                            AS "rooms!",
+                   ARRAY(SELECT g.name
+                         FROM lesson_group lg
+                                  JOIN "group" g ON lg.group_id = g.id
+                         WHERE lg.lesson_id = l.id
+                         ORDER BY g.name)                            AS "groups!"
+            FROM lesson l
+                     LEFT JOIN subject s ON l.subject_id = s.id
+            WHERE l.import_id = $1
+            ORDER BY l.day, l.time
+            "#,
+            import_id
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(LessonEntry {
+                teachers: row.teachers,
+                day: row.day.try_into()?,
+                time: row.time,
+                duration_minutes: row.duration_minutes,
+                rooms: row.rooms,
+                groups: row.groups,
+                subject: row.subject,
+                week: row.week,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+        let availabilities = sqlx::query!(
+            r#"
+            SELECT t.full_name                AS teacher,
+                   av.day::smallint           AS "day!",
+                   av.time::time              AS "time!",
+                   av.availability_type       AS "availability_type: AvailabilityType",
+                   av.week                    AS "week: Week",
+                   r.name                     AS "room?",
+                   (EXTRACT(EPOCH FROM av.duration) / 60)::integer AS "duration_minutes!"
+            FROM availability av
+                     JOIN teacher t ON av.teacher_id = t.id
+                     LEFT JOIN room r ON av.room_id = r.id
+            WHERE t.import_id = $1
+            ORDER BY t.full_name, av.day, av.time
+            "#,
+            import_id
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(AvailabilityEntry {
+                teacher: row.teacher,
+                day: row.day.try_into()?,
+                time: row.time,
+                availability_type: row.availability_type,
+                week: row.week,
+                room: row.room,
+                duration_minutes: row.duration_minutes,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            teachers,
+            rooms,
+            groups,
+            subjects,
+            lessons,
+            availabilities,
+        })
+    }
+
+    pub fn counts(&self) -> ImportCounts {
+        ImportCounts {
+            teachers: self.teachers.len(),
+            rooms: self.rooms.len(),
+            groups: self.groups.len(),
+            subjects: self.subjects.len(),
+            lessons: self.lessons.len(),
+            availabilities: self.availabilities.len(),
+        }
+    }
+}
@@ -1,10 +1,15 @@
 use axum::{extract::Path, response::IntoResponse};
-use axum_serde::macros::Deserialize;
+use axum_serde::{Sonic, macros::Deserialize};
 use http::StatusCode;
 use tracing::error;
 use utoipa::IntoParams;
 
-use crate::{app::openapi::IMPORT_TAG, users::AuthSession};
+use crate::{
+    app::openapi::IMPORT_TAG,
+    types::ImportStatus,
+    users::AuthSession,
+    web::endpoints::protected::import::get::ImportInfo,
+};
 
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct ImportDeletionPathParams {
@@ -17,8 +22,9 @@ pub struct ImportDeletionPathParams {
     summary = "Delete an import",
     params(ImportDeletionPathParams),
     responses(
-        (status = OK, description = "The import was deleted"),
+        (status = OK, description = "The deleted import", body = ImportInfo),
         (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Import not found or not accessible"),
     ),
     security(
         ("session" = [])
@@ -33,23 +39,30 @@ pub async fn delete(
         return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
     };
 
-    match sqlx::query!(
+    match sqlx::query_as!(
+        ImportInfo,
         r#"
         DELETE FROM import
         WHERE id = $1 AND user_id = $2
+        RETURNING id,
+                  file_name,
+                  begin_ts,
+                  end_ts,
+                  import_ts,
+                  status AS "status: ImportStatus",
+                  active
         "#,
         req.import_id,
         user.id,
     )
-    .execute(&auth_session.backend.db)
+    .fetch_optional(&auth_session.backend.db)
     .await
     {
-        Ok(_) => {}
+        Ok(Some(deleted)) => Sonic(deleted).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Import not found").into_response(),
         Err(e) => {
             error!("Database error when deleting the import: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
         }
-    };
-
-    StatusCode::OK.into_response()
+    }
 }
@@ -0,0 +1,149 @@
+use axum::{extract::Path, response::IntoResponse};
+use axum_serde::Sonic;
+use chrono::NaiveDate;
+use color_eyre::Result;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    app::openapi::IMPORT_TAG,
+    types::{ImportStatus, IsoDow},
+    users::AuthSession,
+    web::endpoints::protected::import::{
+        contents::{AvailabilityEntry, ImportContents, ImportCounts, LessonEntry},
+        get::ImportInfo,
+    },
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImportDetailPathParams {
+    import_id: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ImportDetail {
+    #[serde(flatten)]
+    info: ImportInfo,
+    /// Monday of a week A, for timetables alternating A/B weeks
+    week_a_start: Option<NaiveDate>,
+    /// Username of who uploaded the file
+    uploaded_by: String,
+    counts: ImportCounts,
+    teachers: Vec<String>,
+    groups: Vec<String>,
+    rooms: Vec<String>,
+    subjects: Vec<String>,
+    /// Lessons by day, from Monday, each day sorted by time
+    days: Vec<DayLessons>,
+    availabilities: Vec<AvailabilityEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DayLessons {
+    day: IsoDow,
+    lessons: Vec<LessonEntry>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/{import_id}",
+    summary = "Get an import",
+    description = "The import with everything it contains, e.g. to check it before activating it. \
+                   Imports still being processed have no contents yet.",
+    params(ImportDetailPathParams),
+    responses(
+        (status = OK, description = "The import and its contents", body = ImportDetail),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Import not found or not accessible"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = IMPORT_TAG,
+)]
+pub async fn detail(
+    auth_session: AuthSession,
+    Path(path): Path<ImportDetailPathParams>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let res: Result<Option<ImportDetail>> = async {
+        let mut conn = auth_session.backend.db.acquire().await?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT i.id,
+                   i.file_name,
+                   i.begin_ts,
+                   i.end_ts,
+                   i.import_ts,
+                   i.status AS "status: ImportStatus",
+                   i.active,
+                   i.week_a_start,
+                   u.username
+            FROM import i
+                     JOIN "user" u ON i.user_id = u.id
+            WHERE i.id = $1
+              AND i.user_id = $2
+            "#,
+            path.import_id,
+            user.id,
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let contents = ImportContents::load(&mut conn, path.import_id).await?;
+        let counts = contents.counts();
+
+        // Lessons are sorted by day, then time
+        let mut days: Vec<DayLessons> = Vec::new();
+        for lesson in contents.lessons {
+            match days.last_mut() {
+                Some(day) if day.day == lesson.day => day.lessons.push(lesson),
+                _ => days.push(DayLessons {
+                    day: lesson.day,
+                    lessons: vec![lesson],
+                }),
+            }
+        }
+
+        Ok(Some(ImportDetail {
+            info: ImportInfo {
+                id: row.id,
+                file_name: row.file_name,
+                begin_ts: row.begin_ts,
+                end_ts: row.end_ts,
+                import_ts: row.import_ts,
+                status: row.status,
+                active: row.active,
+            },
+            week_a_start: row.week_a_start,
+            uploaded_by: row.username,
+            counts,
+            teachers: contents.teachers,
+            groups: contents.groups,
+            rooms: contents.rooms,
+            subjects: contents.subjects,
+            days,
+            availabilities: contents.availabilities,
+        }))
+    }
+    .await;
+
+    match res {
+        Ok(Some(detail)) => Sonic(detail).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Import not found").into_response(),
+        Err(e) => {
+            error!("Error when getting the import: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
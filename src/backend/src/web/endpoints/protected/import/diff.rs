@@ -0,0 +1,420 @@
+use ahash::{AHashMap, AHashSet};
+use axum::{
+    extract::{Path, Query},
+    response::IntoResponse,
+};
+use axum_serde::Sonic;
+use color_eyre::Result;
+use http::{StatusCode, header};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    app::openapi::IMPORT_TAG,
+    users::AuthSession,
+    web::endpoints::protected::import::contents::{
+        AvailabilityEntry, ImportContents, LessonEntry,
+    },
+};
+
+/// Differences between an older and a newer import.
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ImportDiff {
+    pub teachers_added: Vec<String>,
+    pub teachers_removed: Vec<String>,
+    pub rooms_added: Vec<String>,
+    pub rooms_removed: Vec<String>,
+    pub groups_added: Vec<String>,
+    pub groups_removed: Vec<String>,
+    pub lessons_added: Vec<LessonEntry>,
+    pub lessons_removed: Vec<LessonEntry>,
+    pub lessons_moved: Vec<LessonMove>,
+    pub availability_changes: Vec<AvailabilityChange>,
+}
+
+/// The same lesson (teachers, groups and subject) at a different day, time
+/// or room.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LessonMove {
+    pub before: LessonEntry,
+    pub after: LessonEntry,
+}
+
+/// Availabilities of a teacher that were added or removed
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AvailabilityChange {
+    pub teacher: String,
+    pub added: Vec<AvailabilityEntry>,
+    pub removed: Vec<AvailabilityEntry>,
+}
+
+impl AvailabilityChange {
+    fn new(teacher: &str) -> Self {
+        Self {
+            teacher: teacher.to_owned(),
+            added: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+}
+
+/// What a lesson is, regardless of when and where it takes place
+type LessonKey<'a> = (&'a [String], &'a [String], Option<&'a str>);
+
+fn lesson_key(lesson: &LessonEntry) -> LessonKey<'_> {
+    (&lesson.teachers, &lesson.groups, lesson.subject.as_deref())
+}
+
+impl ImportDiff {
+    pub fn between(old: &ImportContents, new: &ImportContents) -> Self {
+        let mut diff = Self {
+            teachers_added: names_only_in(&new.teachers, &old.teachers),
+            teachers_removed: names_only_in(&old.teachers, &new.teachers),
+            rooms_added: names_only_in(&new.rooms, &old.rooms),
+            rooms_removed: names_only_in(&old.rooms, &new.rooms),
+            groups_added: names_only_in(&new.groups, &old.groups),
+            groups_removed: names_only_in(&old.groups, &new.groups),
+            availability_changes: availability_changes(old, new),
+            ..Default::default()
+        };
+
+        let mut old_lessons: AHashMap<LessonKey, Vec<&LessonEntry>> = AHashMap::new();
+        for lesson in &old.lessons {
+            old_lessons.entry(lesson_key(lesson)).or_default().push(lesson);
+        }
+
+        let mut new_lessons: AHashMap<LessonKey, Vec<&LessonEntry>> = AHashMap::new();
+        for lesson in &new.lessons {
+            new_lessons.entry(lesson_key(lesson)).or_default().push(lesson);
+        }
+
+        for (key, mut after) in new_lessons {
+            let mut before = old_lessons.remove(&key).unwrap_or_default();
+
+            // Lessons that did not change at all
+            after.retain(|lesson| match before.iter().position(|old| old == lesson) {
+                Some(index) => {
+                    before.swap_remove(index);
+                    false
+                }
+                None => true,
+            });
+
+            before.sort_by_key(|l| (l.day, l.time));
+            after.sort_by_key(|l| (l.day, l.time));
+
+            let moved = before.len().min(after.len());
+
+            for (before, after) in before.iter().zip(&after) {
+                diff.lessons_moved.push(LessonMove {
+                    before: (*before).clone(),
+                    after: (*after).clone(),
+                });
+            }
+
+            diff.lessons_removed.extend(before.into_iter().skip(moved).cloned());
+            diff.lessons_added.extend(after.into_iter().skip(moved).cloned());
+        }
+
+        diff.lessons_removed.extend(old_lessons.into_values().flatten().cloned());
+
+        diff.lessons_added.sort_by_key(|l| (l.day, l.time));
+        diff.lessons_removed.sort_by_key(|l| (l.day, l.time));
+        diff.lessons_moved.sort_by_key(|m| (m.before.day, m.before.time));
+
+        diff
+    }
+}
+
+/// A row of the CSV version of the diff
+#[derive(Debug, Serialize)]
+struct DiffCsvRow<'a> {
+    entity: &'static str,
+    change: &'static str,
+    name: String,
+    groups: String,
+    subject: &'a str,
+    before: String,
+    after: String,
+}
+
+impl<'a> DiffCsvRow<'a> {
+    fn named(entity: &'static str, change: &'static str, name: &str) -> Self {
+        Self {
+            entity,
+            change,
+            name: name.to_owned(),
+            groups: String::new(),
+            subject: "",
+            before: String::new(),
+            after: String::new(),
+        }
+    }
+
+    fn lesson(change: &'static str, lesson: &'a LessonEntry) -> Self {
+        Self {
+            entity: "lesson",
+            change,
+            name: lesson.teachers.join(", "),
+            groups: lesson.groups.join(", "),
+            subject: lesson.subject.as_deref().unwrap_or_default(),
+            before: String::new(),
+            after: String::new(),
+        }
+    }
+}
+
+fn describe_lesson(lesson: &LessonEntry) -> String {
+    let mut description = format!(
+        "{} {} {}min {}",
+        lesson.day,
+        lesson.time.format("%H:%M"),
+        lesson.duration_minutes,
+        lesson.rooms.join(", ")
+    );
+
+    if let Some(week) = lesson.week {
+        description.push_str(&format!(" week {week}"));
+    }
+
+    description
+}
+
+fn describe_availability(availability: &AvailabilityEntry) -> String {
+    let mut description = format!(
+        "{} {} {}",
+        availability.day,
+        availability.time.format("%H:%M"),
+        availability.availability_type
+    );
+
+    if let Some(week) = availability.week {
+        description.push_str(&format!(" week {week}"));
+    }
+
+    description
+}
+
+impl ImportDiff {
+    pub fn to_csv(&self) -> Result<Vec<u8>> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+
+        let names = [
+            ("teacher", "added", &self.teachers_added),
+            ("teacher", "removed", &self.teachers_removed),
+            ("room", "added", &self.rooms_added),
+            ("room", "removed", &self.rooms_removed),
+            ("group", "added", &self.groups_added),
+            ("group", "removed", &self.groups_removed),
+        ];
+
+        for (entity, change, names) in names {
+            for name in names {
+                writer.serialize(DiffCsvRow::named(entity, change, name))?;
+            }
+        }
+
+        for lesson in &self.lessons_added {
+            writer.serialize(DiffCsvRow {
+                after: describe_lesson(lesson),
+                ..DiffCsvRow::lesson("added", lesson)
+            })?;
+        }
+
+        for lesson in &self.lessons_removed {
+            writer.serialize(DiffCsvRow {
+                before: describe_lesson(lesson),
+                ..DiffCsvRow::lesson("removed", lesson)
+            })?;
+        }
+
+        for moved in &self.lessons_moved {
+            writer.serialize(DiffCsvRow {
+                before: describe_lesson(&moved.before),
+                after: describe_lesson(&moved.after),
+                ..DiffCsvRow::lesson("moved", &moved.after)
+            })?;
+        }
+
+        for change in &self.availability_changes {
+            for availability in &change.added {
+                writer.serialize(DiffCsvRow {
+                    after: describe_availability(availability),
+                    ..DiffCsvRow::named("availability", "added", &change.teacher)
+                })?;
+            }
+
+            for availability in &change.removed {
+                writer.serialize(DiffCsvRow {
+                    before: describe_availability(availability),
+                    ..DiffCsvRow::named("availability", "removed", &change.teacher)
+                })?;
+            }
+        }
+
+        Ok(writer.into_inner()?)
+    }
+}
+
+fn availability_changes(old: &ImportContents, new: &ImportContents) -> Vec<AvailabilityChange> {
+    let old_availabilities: AHashSet<&AvailabilityEntry> = old.availabilities.iter().collect();
+    let new_availabilities: AHashSet<&AvailabilityEntry> = new.availabilities.iter().collect();
+
+    let mut changes: AHashMap<&str, AvailabilityChange> = AHashMap::new();
+
+    for availability in new_availabilities.difference(&old_availabilities) {
+        changes
+            .entry(&availability.teacher)
+            .or_insert_with(|| AvailabilityChange::new(&availability.teacher))
+            .added
+            .push((*availability).clone());
+    }
+
+    for availability in old_availabilities.difference(&new_availabilities) {
+        changes
+            .entry(&availability.teacher)
+            .or_insert_with(|| AvailabilityChange::new(&availability.teacher))
+            .removed
+            .push((*availability).clone());
+    }
+
+    let mut changes: Vec<AvailabilityChange> = changes.into_values().collect();
+
+    changes.sort_unstable_by(|a, b| a.teacher.cmp(&b.teacher));
+
+    for change in &mut changes {
+        change.added.sort_by_key(|a| (a.day, a.time));
+        change.removed.sort_by_key(|a| (a.day, a.time));
+    }
+
+    changes
+}
+
+fn names_only_in(names: &[String], other: &[String]) -> Vec<String> {
+    let other: AHashSet<&String> = other.iter().collect();
+
+    names
+        .iter()
+        .filter(|name| !other.contains(name))
+        .cloned()
+        .collect()
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImportDiffPathParams {
+    /// The older import
+    import_id: i32,
+    /// The newer import
+    other_import_id: i32,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImportDiffQuery {
+    /// Format of the response, CSV is returned as a downloadable file
+    #[param(default = DiffFormat::default)]
+    #[serde(default)]
+    format: DiffFormat,
+}
+
+#[utoipa::path(
+    get,
+    path = "/{import_id}/diff/{other_import_id}",
+    summary = "Compare two imports",
+    description = "Teachers are matched by full name, rooms and groups by name. \
+                   A lesson is moved when the same teachers teach the same subject \
+                   to the same groups at another day, time or room.",
+    params(ImportDiffPathParams, ImportDiffQuery),
+    responses(
+        (status = OK, description = "Differences between the imports", content(
+            (ImportDiff = "application/json"),
+            (String = "text/csv"),
+        )),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Import not found or not accessible"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = IMPORT_TAG,
+)]
+pub async fn diff(
+    auth_session: AuthSession,
+    Path(path): Path<ImportDiffPathParams>,
+    Query(query): Query<ImportDiffQuery>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let res: Result<Option<ImportDiff>> = async {
+        let mut conn = auth_session.backend.db.acquire().await?;
+
+        let owned = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM import
+            WHERE id IN ($1, $2)
+              AND user_id = $3
+            "#,
+            path.import_id,
+            path.other_import_id,
+            user.id,
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let expected = if path.import_id == path.other_import_id { 1 } else { 2 };
+
+        if owned != expected {
+            return Ok(None);
+        }
+
+        let old = ImportContents::load(&mut conn, path.import_id).await?;
+        let new = ImportContents::load(&mut conn, path.other_import_id).await?;
+
+        Ok(Some(ImportDiff::between(&old, &new)))
+    }
+    .await;
+
+    let diff = match res {
+        Ok(Some(diff)) => diff,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Import not found").into_response(),
+        Err(e) => {
+            error!("Error when comparing the imports: {:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+        }
+    };
+
+    match query.format {
+        DiffFormat::Json => Sonic(diff).into_response(),
+        DiffFormat::Csv => match diff.to_csv() {
+            Ok(csv) => (
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_owned()),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        format!(
+                            "attachment; filename=\"import-{}-{}-diff.csv\"",
+                            path.import_id, path.other_import_id
+                        ),
+                    ),
+                ],
+                csv,
+            )
+                .into_response(),
+            Err(e) => {
+                error!("Error when writing the diff as CSV: {:?}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+            }
+        },
+    }
+}
@@ -0,0 +1,83 @@
+use axum::{extract::Path, response::IntoResponse};
+use axum_serde::Xml;
+use color_eyre::Result;
+use http::{StatusCode, header};
+use serde::Deserialize;
+use tracing::error;
+use utoipa::IntoParams;
+
+use crate::{
+    app::openapi::IMPORT_TAG,
+    users::AuthSession,
+    web::endpoints::protected::import::{contents::ImportContents, post::ScheduleFile},
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImportExportPathParams {
+    import_id: i32,
+}
+
+#[utoipa::path(
+    get,
+    path = "/{import_id}/export",
+    summary = "Export an import",
+    description = "The lessons and availabilities of the import as an OrarioFacile XML file, \
+                   which can be imported again.",
+    params(ImportExportPathParams),
+    responses(
+        (status = OK, description = "The import as a downloadable file", body = ScheduleFile, content_type = "application/xml"),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Import not found or not accessible"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = IMPORT_TAG,
+)]
+pub async fn export(
+    auth_session: AuthSession,
+    Path(path): Path<ImportExportPathParams>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let res: Result<Option<ScheduleFile>> = async {
+        let mut conn = auth_session.backend.db.acquire().await?;
+
+        let import = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM import WHERE id = $1 AND user_id = $2
+            "#,
+            path.import_id,
+            user.id,
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        if import.is_none() {
+            return Ok(None);
+        }
+
+        let contents = ImportContents::load(&mut conn, path.import_id).await?;
+
+        Ok(Some(ScheduleFile::from_contents(contents)))
+    }
+    .await;
+
+    match res {
+        Ok(Some(file)) => (
+            [(
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"import-{}.xml\"", path.import_id),
+            )],
+            Xml(file),
+        )
+            .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Import not found").into_response(),
+        Err(e) => {
+            error!("Error when exporting the import: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
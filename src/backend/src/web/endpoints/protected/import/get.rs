@@ -6,14 +6,19 @@ use serde::Serialize;
 use tracing::error;
 use utoipa::ToSchema;
 
-use crate::{app::openapi::IMPORT_TAG, users::AuthSession};
+use crate::{app::openapi::IMPORT_TAG, types::ImportStatus, users::AuthSession};
 
 #[derive(Debug, Serialize, ToSchema)]
-struct ImportInfo {
-    id: i32,
-    file_name: String,
-    begin_ts: NaiveDateTime,
-    end_ts: NaiveDateTime,
+pub struct ImportInfo {
+    pub(super) id: i32,
+    pub(super) file_name: String,
+    pub(super) begin_ts: NaiveDateTime,
+    pub(super) end_ts: NaiveDateTime,
+    /// When the file was uploaded
+    pub(super) import_ts: NaiveDateTime,
+    pub(super) status: ImportStatus,
+    /// Used on its days over more recent imports
+    pub(super) active: bool,
 }
 
 #[utoipa::path(
@@ -37,7 +42,13 @@ pub async fn get(auth_session: AuthSession) -> impl IntoResponse {
     let imports = match sqlx::query_as!(
         ImportInfo,
         r#"
-        SELECT i.id, i.file_name, i.begin_ts, i.end_ts
+        SELECT i.id,
+               i.file_name,
+               i.begin_ts,
+               i.end_ts,
+               i.import_ts,
+               i.status AS "status: ImportStatus",
+               i.active
         FROM import i
         WHERE i.user_id = $1
         ORDER BY i.begin_ts DESC
@@ -1,10 +1,33 @@
+use axum::extract::DefaultBodyLimit;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
+mod carry_over;
+mod contents;
 mod delete;
+mod detail;
+pub mod diff;
+mod export;
 mod get;
+mod overlap;
 mod patch;
 pub mod post;
+mod status;
+mod weeks;
 
 pub fn router() -> OpenApiRouter {
-    OpenApiRouter::new().routes(routes!(post::post, get::get, delete::delete, patch::patch))
+    OpenApiRouter::new()
+        .routes(routes!(get::get, delete::delete, patch::patch))
+        .routes(routes!(detail::detail))
+        .routes(routes!(weeks::get_weeks, weeks::put_weeks))
+        .routes(routes!(diff::diff))
+        .routes(routes!(export::export))
+        .routes(routes!(status::status))
+        .routes(routes!(carry_over::carry_over))
+        .merge(
+            OpenApiRouter::new()
+                .routes(routes!(post::post))
+                .routes(routes!(post::upload::upload))
+                // Leave room for the other form fields, the file size is checked by the handler
+                .layer(DefaultBodyLimit::max(*post::MAX_FILE_SIZE + 64 * 1024)),
+        )
 }
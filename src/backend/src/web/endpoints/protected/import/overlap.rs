@@ -0,0 +1,76 @@
+use chrono::NaiveDateTime;
+use color_eyre::Result;
+use serde::Serialize;
+use sqlx::PgConnection;
+use utoipa::ToSchema;
+
+/// Another import valid on some of the same days. The dashboard uses the
+/// active one of them, otherwise the most recent.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OverlappingImport {
+    id: i32,
+    file_name: String,
+    begin_ts: NaiveDateTime,
+    end_ts: NaiveDateTime,
+    active: bool,
+}
+
+/// The imports of the user, failed ones excepted, sharing days with the
+/// validity of `import_id`.
+pub async fn overlapping_imports(
+    conn: &mut PgConnection,
+    import_id: i32,
+    user_id: i32,
+) -> Result<Vec<OverlappingImport>> {
+    let overlaps = sqlx::query_as!(
+        OverlappingImport,
+        r#"
+        SELECT other.id, other.file_name, other.begin_ts, other.end_ts, other.active
+        FROM import other
+                 JOIN import target ON target.id = $1
+        WHERE other.user_id = $2
+          AND target.user_id = $2
+          AND other.id <> target.id
+          AND other.status <> 'Failed'
+          AND other.begin_ts::date <= target.end_ts::date
+          AND other.end_ts::date >= target.begin_ts::date
+        ORDER BY other.begin_ts, other.id
+        "#,
+        import_id,
+        user_id,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(overlaps)
+}
+
+/// Only one import is active on a day: if `import_id` is active, the other
+/// active imports overlapping it are deactivated.
+pub async fn deactivate_overlapping(
+    conn: &mut PgConnection,
+    import_id: i32,
+    user_id: i32,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE import other
+        SET active = FALSE
+        FROM import target
+        WHERE target.id = $1
+          AND target.user_id = $2
+          AND target.active
+          AND other.user_id = $2
+          AND other.id <> target.id
+          AND other.active
+          AND other.begin_ts::date <= target.end_ts::date
+          AND other.end_ts::date >= target.begin_ts::date
+        "#,
+        import_id,
+        user_id,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
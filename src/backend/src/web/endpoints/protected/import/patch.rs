@@ -1,11 +1,23 @@
 use axum::{extract::Path, response::IntoResponse};
 use axum_serde::{Sonic, macros::Deserialize};
-use chrono::NaiveDateTime;
+use axum_thiserror::ErrorStatus;
+use chrono::{NaiveDate, NaiveDateTime};
+use color_eyre::Report;
 use http::StatusCode;
+use serde::{Deserializer, Serialize};
+use thiserror::Error;
 use tracing::error;
 use utoipa::{IntoParams, ToSchema};
 
-use crate::{app::openapi::IMPORT_TAG, users::AuthSession};
+use crate::{
+    app::openapi::IMPORT_TAG,
+    types::ImportStatus,
+    users::AuthSession,
+    web::endpoints::protected::import::{
+        get::ImportInfo,
+        overlap::{OverlappingImport, deactivate_overlapping, overlapping_imports},
+    },
+};
 
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct ImportPatchPathParams {
@@ -16,6 +28,47 @@ pub struct ImportPatchPathParams {
 pub struct ImportPatchRequest {
     begin_ts: Option<NaiveDateTime>,
     end_ts: Option<NaiveDateTime>,
+    /// Any day of a week that is week A, for timetables alternating A/B weeks.
+    /// `null` clears it, the import then no longer alternates.
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<NaiveDate>)]
+    week_a_start: Option<Option<NaiveDate>>,
+    /// Use this import on its days even if more recent imports cover them.
+    /// The active imports it overlaps are deactivated.
+    active: Option<bool>,
+}
+
+/// Tells a field set to `null`, `Some(None)`, from a missing one, `None`
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    serde::Deserialize::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PatchedImport {
+    #[serde(flatten)]
+    info: ImportInfo,
+    /// Other imports valid on some of the same days
+    overlaps: Vec<OverlappingImport>,
+}
+
+#[derive(Error, Debug, ErrorStatus)]
+enum ImportPatchError {
+    #[error("Import not found")]
+    #[status(StatusCode::NOT_FOUND)]
+    NotFound,
+    #[error("end_ts must not be before begin_ts")]
+    #[status(StatusCode::UNPROCESSABLE_ENTITY)]
+    InvalidRange,
+    #[error("Internal Server Error")]
+    #[status(StatusCode::INTERNAL_SERVER_ERROR)]
+    Database(#[from] sqlx::Error),
+    #[error("Internal Server Error")]
+    #[status(StatusCode::INTERNAL_SERVER_ERROR)]
+    Other(#[from] Report),
 }
 
 #[utoipa::path(
@@ -25,8 +78,10 @@ pub struct ImportPatchRequest {
     params(ImportPatchPathParams),
     request_body = ImportPatchRequest,
     responses(
-        (status = OK, description = "The import was patched"),
+        (status = OK, description = "The patched import, with the other imports valid on some of the same days", body = PatchedImport),
         (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Import not found or not accessible"),
+        (status = UNPROCESSABLE_ENTITY, description = "The import would end before it begins", example = "end_ts must not be before begin_ts"),
     ),
     security(
         ("session" = [])
@@ -42,27 +97,76 @@ pub async fn patch(
         return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
     };
 
-    match sqlx::query!(
-        r#"
-        UPDATE import
-        SET begin_ts = COALESCE($2, begin_ts),
-            end_ts = COALESCE($3, end_ts)
-        WHERE id = $1 AND user_id = $4
-        "#,
-        path.import_id,
-        req.begin_ts,
-        req.end_ts,
-        user.id,
-    )
-    .execute(&auth_session.backend.db)
-    .await
-    {
-        Ok(_) => {}
-        Err(e) => {
-            error!("Database error when patching the import: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+    let res: Result<PatchedImport, ImportPatchError> = async {
+        let mut txn = auth_session.backend.db.begin().await?;
+
+        let current = sqlx::query!(
+            r#"
+            SELECT begin_ts, end_ts
+            FROM import
+            WHERE id = $1 AND user_id = $2
+            FOR UPDATE
+            "#,
+            path.import_id,
+            user.id,
+        )
+        .fetch_optional(&mut *txn)
+        .await?
+        .ok_or(ImportPatchError::NotFound)?;
+
+        // Checked here rather than by the constraint, to tell the client
+        if req.end_ts.unwrap_or(current.end_ts) < req.begin_ts.unwrap_or(current.begin_ts) {
+            return Err(ImportPatchError::InvalidRange);
         }
-    };
 
-    StatusCode::OK.into_response()
+        let info = sqlx::query_as!(
+            ImportInfo,
+            r#"
+            UPDATE import
+            SET begin_ts = COALESCE($2, begin_ts),
+                end_ts = COALESCE($3, end_ts),
+                week_a_start = CASE
+                                   WHEN $7 THEN DATE_TRUNC('week', $5::date)::date
+                                   ELSE week_a_start
+                    END,
+                active = COALESCE($6, active)
+            WHERE id = $1 AND user_id = $4
+            RETURNING id,
+                      file_name,
+                      begin_ts,
+                      end_ts,
+                      import_ts,
+                      status AS "status: ImportStatus",
+                      active
+            "#,
+            path.import_id,
+            req.begin_ts,
+            req.end_ts,
+            user.id,
+            req.week_a_start.flatten(),
+            req.active,
+            req.week_a_start.is_some(),
+        )
+        .fetch_one(&mut *txn)
+        .await?;
+
+        // New dates can overlap other active imports too
+        deactivate_overlapping(&mut txn, path.import_id, user.id).await?;
+
+        let overlaps = overlapping_imports(&mut txn, path.import_id, user.id).await?;
+
+        txn.commit().await?;
+
+        Ok(PatchedImport { info, overlaps })
+    }
+    .await;
+
+    match res {
+        Ok(patched) => Sonic(patched).into_response(),
+        Err(e @ (ImportPatchError::Database(_) | ImportPatchError::Other(_))) => {
+            error!("Error when patching the import: {:?}", e);
+            e.into_response()
+        }
+        Err(e) => e.into_response(),
+    }
 }
@@ -0,0 +1,326 @@
+use ahash::AHashMap;
+use serde::Deserialize;
+
+use crate::web::endpoints::protected::import::post::{
+    decode::UploadError,
+    importer::{ItaDay, RawLesson, ScheduleFile},
+    parser::{
+        AVAILABILITY_SUBJECTS, DISPOSITION_ROOM, ScheduleParser, format_duration, parse_time,
+    },
+};
+
+const DAYS: [ItaDay; 7] = [
+    ItaDay::Lun,
+    ItaDay::Mar,
+    ItaDay::Mer,
+    ItaDay::Gio,
+    ItaDay::Ven,
+    ItaDay::Sab,
+    ItaDay::Dom,
+];
+
+// <timetable ascttversion="2024.1.2">
+//   <periods>
+//     <period period="1" starttime="8:00" endtime="9:00"/>
+//   </periods>
+//   <subjects><subject id="S1" name="INFORMATICA" short="INF"/></subjects>
+//   <teachers><teacher id="T1" name="SCIALPI MARIO" short="SM"/></teachers>
+//   <classrooms><classroom id="R1" name="07-TW" short="07"/></classrooms>
+//   <classes><class id="C1" name="5^A-IA" short="5A"/></classes>
+//   <lessons>
+//     <lesson id="L1" classids="C1" subjectid="S1" periodspercard="1"
+//             teacherids="T1" classroomids="R1"/>
+//   </lessons>
+//   <cards>
+//     <card lessonid="L1" period="1" days="10000" weeks="10" classroomids="R1"/>
+//   </cards>
+// </timetable>
+#[derive(Debug, Deserialize)]
+struct AscTimetable {
+    #[serde(default)]
+    periods: AscPeriods,
+    #[serde(default)]
+    subjects: AscSubjects,
+    #[serde(default)]
+    teachers: AscTeachers,
+    #[serde(default)]
+    classrooms: AscClassrooms,
+    #[serde(default)]
+    classes: AscClasses,
+    #[serde(default)]
+    lessons: AscLessons,
+    #[serde(default)]
+    cards: AscCards,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AscPeriods {
+    #[serde(default)]
+    period: Vec<AscPeriod>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AscSubjects {
+    #[serde(default)]
+    subject: Vec<AscEntity>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AscTeachers {
+    #[serde(default)]
+    teacher: Vec<AscEntity>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AscClassrooms {
+    #[serde(default)]
+    classroom: Vec<AscEntity>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AscClasses {
+    #[serde(default)]
+    class: Vec<AscEntity>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AscLessons {
+    #[serde(default)]
+    lesson: Vec<AscLesson>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AscCards {
+    #[serde(default)]
+    card: Vec<AscCard>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AscPeriod {
+    #[serde(rename = "@period")]
+    period: String,
+    #[serde(rename = "@starttime")]
+    start_time: String,
+    #[serde(rename = "@endtime")]
+    end_time: String,
+}
+
+/// Teachers, classes, classrooms and subjects
+#[derive(Debug, Deserialize)]
+struct AscEntity {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "@name")]
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AscLesson {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "@classids", default)]
+    class_ids: String,
+    #[serde(rename = "@subjectid", default)]
+    subject_id: String,
+    #[serde(rename = "@periodspercard", default)]
+    periods_per_card: Option<String>,
+    #[serde(rename = "@teacherids", default)]
+    teacher_ids: String,
+    #[serde(rename = "@classroomids", default)]
+    classroom_ids: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AscCard {
+    #[serde(rename = "@lessonid")]
+    lesson_id: String,
+    #[serde(rename = "@period")]
+    period: String,
+    /// One digit per day starting from Monday, e.g. 01000 is Tuesday
+    #[serde(rename = "@days")]
+    days: String,
+    /// One digit per week, 10 is week A and 01 week B
+    #[serde(rename = "@weeks", default)]
+    weeks: String,
+    /// Overrides the classrooms of the lesson if set
+    #[serde(rename = "@classroomids", default)]
+    classroom_ids: String,
+}
+
+/// Whether the file is an aSc export rather than an OrarioFacile one, both are
+/// XML.
+pub fn is_asc_export(text: &str) -> bool {
+    // Skip the declaration and comments
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            rest = &rest[1..];
+            continue;
+        }
+
+        return rest.starts_with("<timetable");
+    }
+
+    false
+}
+
+/// The XML export of aSc Timetables
+pub struct AscParser;
+
+impl ScheduleParser for AscParser {
+    fn parse(&self, text: &str) -> Result<ScheduleFile, UploadError> {
+        let timetable: AscTimetable = quick_xml::de::from_str(text)?;
+
+        let names = |entities: &[AscEntity]| -> AHashMap<String, String> {
+            entities
+                .iter()
+                .map(|e| (e.id.clone(), e.name.clone()))
+                .collect()
+        };
+
+        let subjects = names(&timetable.subjects.subject);
+        let teachers = names(&timetable.teachers.teacher);
+        let classrooms = names(&timetable.classrooms.classroom);
+        let classes = names(&timetable.classes.class);
+
+        let periods: AHashMap<&str, &AscPeriod> = timetable
+            .periods
+            .period
+            .iter()
+            .map(|p| (p.period.as_str(), p))
+            .collect();
+
+        let lessons: AHashMap<&str, &AscLesson> = timetable
+            .lessons
+            .lesson
+            .iter()
+            .map(|l| (l.id.as_str(), l))
+            .collect();
+
+        let mut raw_lessons = Vec::new();
+
+        for card in &timetable.cards.card {
+            let lesson = lessons.get(card.lesson_id.as_str()).ok_or_else(|| {
+                UploadError::InvalidAsc(format!("Card of unknown lesson {}", card.lesson_id))
+            })?;
+            let period = periods.get(card.period.as_str()).ok_or_else(|| {
+                UploadError::InvalidAsc(format!("Card in unknown period {}", card.period))
+            })?;
+
+            let time = parse_time(&period.start_time).map_err(UploadError::InvalidAsc)?;
+            let duration = card_duration(lesson, period, &periods)?;
+
+            let teacher = resolve(&lesson.teacher_ids, &teachers);
+            let group = resolve(&lesson.class_ids, &classes);
+            let room = if card.classroom_ids.is_empty() {
+                resolve(&lesson.classroom_ids, &classrooms)
+            } else {
+                resolve(&card.classroom_ids, &classrooms)
+            };
+            let subject = subjects.get(&lesson.subject_id).cloned();
+
+            let week = match card.weeks.as_str() {
+                "10" => Some("A".to_owned()),
+                "01" => Some("B".to_owned()),
+                _ => None,
+            };
+
+            let is_availability = subject
+                .as_deref()
+                .is_some_and(|s| AVAILABILITY_SUBJECTS.contains(&s));
+
+            // A card can be repeated on several days
+            for (index, _) in card.days.char_indices().filter(|(_, d)| *d == '1') {
+                let Some(ita_day) = DAYS.get(index).cloned() else {
+                    return Err(UploadError::InvalidAsc(format!(
+                        "Invalid days: {}",
+                        card.days
+                    )));
+                };
+
+                if is_availability {
+                    // Availabilities are per teacher
+                    for teacher in teacher.iter().flatten() {
+                        raw_lessons.push(RawLesson {
+                            duration: Some(duration.clone()),
+                            subject: subject.clone(),
+                            teacher: Some(vec![teacher.clone()]),
+                            room: Some(vec![DISPOSITION_ROOM.to_owned()]),
+                            week: week.clone(),
+                            ita_day: Some(ita_day.clone()),
+                            time: Some(time),
+                            ..Default::default()
+                        });
+                    }
+                    continue;
+                }
+
+                raw_lessons.push(RawLesson {
+                    duration: Some(duration.clone()),
+                    subject: subject.clone(),
+                    teacher: teacher.clone(),
+                    group: group.clone(),
+                    room: room.clone(),
+                    week: week.clone(),
+                    ita_day: Some(ita_day),
+                    time: Some(time),
+                    ..Default::default()
+                });
+            }
+        }
+
+        Ok(ScheduleFile {
+            lessons: raw_lessons,
+        })
+    }
+}
+
+/// Names of comma separated ids, unknown ids are skipped
+fn resolve(ids: &str, names: &AHashMap<String, String>) -> Option<Vec<String>> {
+    let resolved: Vec<String> = ids
+        .split(',')
+        .filter_map(|id| names.get(id.trim()).cloned())
+        .collect();
+
+    (!resolved.is_empty()).then_some(resolved)
+}
+
+/// From the start of the card's period to the end of its last period, in the
+/// H:MM format of OrarioFacile
+fn card_duration(
+    lesson: &AscLesson,
+    first: &AscPeriod,
+    periods: &AHashMap<&str, &AscPeriod>,
+) -> Result<String, UploadError> {
+    let count: usize = lesson
+        .periods_per_card
+        .as_deref()
+        .map(|p| p.trim().parse())
+        .transpose()
+        .map_err(|_| {
+            UploadError::InvalidAsc(format!("Invalid periodspercard in lesson {}", lesson.id))
+        })?
+        .unwrap_or(1);
+
+    let last = first
+        .period
+        .parse::<usize>()
+        .ok()
+        .map(|p| (p + count.max(1) - 1).to_string())
+        .and_then(|p| periods.get(p.as_str()))
+        .unwrap_or(&first);
+
+    let minutes = (parse_time(&last.end_time).map_err(UploadError::InvalidAsc)?
+        - parse_time(&first.start_time).map_err(UploadError::InvalidAsc)?)
+    .num_minutes();
+
+    // End times before start times, or an unusual numbering of the periods
+    if minutes <= 0 {
+        return Err(UploadError::InvalidAsc(format!("Invalid period {}", first.period)));
+    }
+
+    Ok(format_duration(minutes))
+}
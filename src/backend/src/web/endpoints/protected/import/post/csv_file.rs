@@ -0,0 +1,195 @@
+use csv::{ReaderBuilder, StringRecord};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::web::endpoints::protected::import::post::{
+    decode::UploadError,
+    importer::{ItaDay, RawLesson, ScheduleFile},
+    parser::{DISPOSITION_ROOM, ScheduleParser, parse_time},
+};
+
+/// Where to find each field in a CSV file, by column header. Only the teacher,
+/// day and time columns are required.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(default)]
+pub struct CsvColumns {
+    /// Header of the teacher column, several teachers are separated by list_separator
+    #[param(default = "teacher")]
+    teacher_column: String,
+    /// Header of the day column: LUN to DOM, lunedì to domenica or 1 to 7
+    #[param(default = "day")]
+    day_column: String,
+    /// Header of the time column, e.g. 8:00
+    #[param(default = "time")]
+    time_column: String,
+    /// Header of the duration column, e.g. 1:00
+    #[param(default = "duration")]
+    duration_column: String,
+    #[param(default = "group")]
+    group_column: String,
+    #[param(default = "room")]
+    room_column: String,
+    #[param(default = "subject")]
+    subject_column: String,
+    /// Header of the availability type column: DISPO or RECUPERO_ORARIO for
+    /// availabilities, empty for lessons
+    #[param(default = "availability_type")]
+    availability_type_column: String,
+    /// Header of the week column: A, B or empty
+    #[param(default = "week")]
+    week_column: String,
+    /// Field delimiter, spreadsheets often export with ;
+    #[param(default = ",")]
+    delimiter: String,
+    /// Separator of the teachers, groups and rooms in the same cell
+    #[param(default = "+")]
+    list_separator: String,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            teacher_column: "teacher".to_owned(),
+            day_column: "day".to_owned(),
+            time_column: "time".to_owned(),
+            duration_column: "duration".to_owned(),
+            group_column: "group".to_owned(),
+            room_column: "room".to_owned(),
+            subject_column: "subject".to_owned(),
+            availability_type_column: "availability_type".to_owned(),
+            week_column: "week".to_owned(),
+            delimiter: ",".to_owned(),
+            list_separator: "+".to_owned(),
+        }
+    }
+}
+
+/// Column indexes of the fields, resolved from the headers
+struct ColumnIndexes {
+    teacher: usize,
+    day: usize,
+    time: usize,
+    duration: Option<usize>,
+    group: Option<usize>,
+    room: Option<usize>,
+    subject: Option<usize>,
+    availability_type: Option<usize>,
+    week: Option<usize>,
+}
+
+impl ColumnIndexes {
+    fn new(headers: &StringRecord, columns: &CsvColumns) -> Result<Self, UploadError> {
+        let find = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+        let require = |name: &str| {
+            find(name).ok_or_else(|| UploadError::InvalidCsv(format!("Missing column {name}")))
+        };
+
+        Ok(Self {
+            teacher: require(&columns.teacher_column)?,
+            day: require(&columns.day_column)?,
+            time: require(&columns.time_column)?,
+            duration: find(&columns.duration_column),
+            group: find(&columns.group_column),
+            room: find(&columns.room_column),
+            subject: find(&columns.subject_column),
+            availability_type: find(&columns.availability_type_column),
+            week: find(&columns.week_column),
+        })
+    }
+}
+
+impl ScheduleParser for CsvColumns {
+    fn parse(&self, text: &str) -> Result<ScheduleFile, UploadError> {
+        parse_csv_file(text, self)
+    }
+}
+
+/// Reads a CSV timetable into the same lessons as an OrarioFacile export.
+fn parse_csv_file(text: &str, columns: &CsvColumns) -> Result<ScheduleFile, UploadError> {
+    let delimiter = single_byte("delimiter", &columns.delimiter)?;
+    let list_separator = single_byte("list_separator", &columns.list_separator)? as char;
+
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| UploadError::InvalidCsv(e.to_string()))?
+        .clone();
+    let indexes = ColumnIndexes::new(&headers, columns)?;
+
+    let mut lessons = Vec::new();
+
+    for record in reader.records() {
+        let record = record.map_err(|e| UploadError::InvalidCsv(e.to_string()))?;
+        let line = record.position().map_or(0, |p| p.line());
+        let row_error = |e: String| UploadError::InvalidCsv(format!("Line {line}: {e}"));
+
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+        let list = |index: Option<usize>| {
+            cell(index).map(|value| {
+                value
+                    .split(list_separator)
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        // Skip blank lines left by spreadsheets
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+
+        let ita_day = cell(Some(indexes.day))
+            .map(ItaDay::try_from)
+            .transpose()
+            .map_err(row_error)?;
+        let time = cell(Some(indexes.time))
+            .map(parse_time)
+            .transpose()
+            .map_err(row_error)?;
+
+        let mut room = list(indexes.room);
+        let mut subject = cell(indexes.subject).map(str::to_owned);
+
+        if let Some(availability_type) = cell(indexes.availability_type) {
+            subject = Some(availability_type.to_uppercase());
+            room.get_or_insert_with(Vec::new)
+                .insert(0, DISPOSITION_ROOM.to_owned());
+        }
+
+        lessons.push(RawLesson {
+            duration: cell(indexes.duration).map(str::to_owned),
+            subject,
+            teacher: list(Some(indexes.teacher)),
+            group: list(indexes.group),
+            room,
+            week: cell(indexes.week).map(str::to_uppercase),
+            ita_day,
+            time,
+            ..Default::default()
+        });
+    }
+
+    Ok(ScheduleFile { lessons })
+}
+
+fn single_byte(name: &'static str, value: &str) -> Result<u8, UploadError> {
+    match value.as_bytes() {
+        [byte] => Ok(*byte),
+        _ => Err(UploadError::InvalidField(
+            name,
+            format!("Must be a single ASCII character, found {value:?}"),
+        )),
+    }
+}
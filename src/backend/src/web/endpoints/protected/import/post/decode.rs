@@ -0,0 +1,240 @@
+use std::{
+    io::{Cursor, Read},
+    sync::LazyLock,
+};
+
+use axum_thiserror::ErrorStatus;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use flate2::read::GzDecoder;
+use http::StatusCode;
+use thiserror::Error;
+use utoipa::ToSchema;
+use zip::ZipArchive;
+
+use crate::web::endpoints::protected::import::post::{
+    asc::{AscParser, is_asc_export},
+    csv_file::CsvColumns,
+    importer::ScheduleFile,
+    parser::{OrarioFacileParser, ScheduleParser},
+    untis::UntisOptions,
+};
+
+/// Default for `IMPORT_MAX_FILE_SIZE`, in bytes
+const DEFAULT_MAX_FILE_SIZE: usize = 10 * 1024 * 1024;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Maximum size of an uploaded file, both as sent and once decompressed.
+/// Set with the `IMPORT_MAX_FILE_SIZE` environment variable, in bytes.
+pub static MAX_FILE_SIZE: LazyLock<usize> = LazyLock::new(|| {
+    std::env::var("IMPORT_MAX_FILE_SIZE")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(DEFAULT_MAX_FILE_SIZE)
+});
+
+#[derive(Error, Debug, ErrorStatus)]
+pub enum UploadError {
+    #[error("The file is larger than {} bytes", *MAX_FILE_SIZE)]
+    #[status(StatusCode::PAYLOAD_TOO_LARGE)]
+    TooLarge,
+    #[error("Missing form field: {0}")]
+    #[status(StatusCode::BAD_REQUEST)]
+    MissingField(&'static str),
+    #[error("Invalid form field {0}: {1}")]
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidField(&'static str, String),
+    #[error("Invalid multipart request: {0}")]
+    #[status(StatusCode::BAD_REQUEST)]
+    Multipart(#[from] axum::extract::multipart::MultipartError),
+    #[error("Unsupported content type: {0}")]
+    #[status(StatusCode::UNSUPPORTED_MEDIA_TYPE)]
+    UnsupportedContentType(String),
+    #[error("The archive doesn't contain a {0} file")]
+    #[status(StatusCode::UNSUPPORTED_MEDIA_TYPE)]
+    NoScheduleInArchive(&'static str),
+    #[error("Invalid archive: {0}")]
+    #[status(StatusCode::UNSUPPORTED_MEDIA_TYPE)]
+    InvalidArchive(String),
+    #[error("Invalid XML: {0}")]
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidXml(#[from] quick_xml::de::DeError),
+    #[error("Invalid CSV: {0}")]
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidCsv(String),
+    #[error("Invalid aSc Timetables export: {0}")]
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidAsc(String),
+    #[error("Invalid Untis export: {0}")]
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidUntis(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum ScheduleFormat {
+    /// OrarioFacile or aSc Timetables export, told apart by their root element
+    Xml,
+    /// Spreadsheet, see `CsvColumns`
+    Csv,
+    /// Untis GPU001.TXT timetable, see `UntisOptions`
+    Untis,
+}
+
+impl ScheduleFormat {
+    /// Files without a content type, and archives sent as such, are considered
+    /// XML. Send compressed CSV and Untis files as text/csv and text/plain.
+    pub fn from_content_type(content_type: Option<&str>) -> Result<Self, UploadError> {
+        let Some(content_type) = content_type else {
+            return Ok(Self::Xml);
+        };
+
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        match mime.as_str() {
+            "application/xml" | "text/xml" | "application/zip" | "application/gzip"
+            | "application/octet-stream" => Ok(Self::Xml),
+            "text/csv" => Ok(Self::Csv),
+            "text/plain" => Ok(Self::Untis),
+            _ => Err(UploadError::UnsupportedContentType(mime)),
+        }
+    }
+
+    /// Recognizes .xml, .csv and .txt files, even compressed, e.g. orario.csv.gz
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let name = file_name.to_lowercase();
+        let name = name
+            .strip_suffix(".gz")
+            .or_else(|| name.strip_suffix(".zip"))
+            .unwrap_or(&name);
+
+        [Self::Xml, Self::Csv, Self::Untis]
+            .into_iter()
+            .find(|format| name.ends_with(format.extension()))
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Xml => ".xml",
+            Self::Csv => ".csv",
+            Self::Untis => ".txt",
+        }
+    }
+
+    /// End of the name of the file to import in archives, Untis exports
+    /// contain a file per table
+    fn archive_entry(self) -> &'static str {
+        match self {
+            Self::Untis => "gpu001.txt",
+            _ => self.extension(),
+        }
+    }
+}
+
+/// Decompresses the file if it's a .zip or .gz archive, decodes it to UTF-8
+/// and parses it.
+pub fn decode_schedule_file(
+    bytes: &[u8],
+    format: ScheduleFormat,
+    columns: &CsvColumns,
+    untis: &UntisOptions,
+) -> Result<ScheduleFile, UploadError> {
+    let file = decompress(bytes, format)?;
+    let text = decode_text(&file);
+
+    let parser: &dyn ScheduleParser = match format {
+        ScheduleFormat::Xml if is_asc_export(&text) => &AscParser,
+        ScheduleFormat::Xml => &OrarioFacileParser,
+        ScheduleFormat::Csv => columns,
+        ScheduleFormat::Untis => untis,
+    };
+
+    parser.parse(&text)
+}
+
+/// Archives are recognized by their content, exports are often renamed.
+fn decompress(bytes: &[u8], format: ScheduleFormat) -> Result<Vec<u8>, UploadError> {
+    if bytes.starts_with(ZIP_MAGIC) {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| UploadError::InvalidArchive(e.to_string()))?;
+
+        let index = (0..archive.len())
+            .find(|&i| {
+                archive
+                    .name_for_index(i)
+                    .is_some_and(|name| {
+                        name.to_lowercase().ends_with(format.archive_entry())
+                    })
+            })
+            .ok_or(UploadError::NoScheduleInArchive(format.archive_entry()))?;
+
+        let entry = archive
+            .by_index(index)
+            .map_err(|e| UploadError::InvalidArchive(e.to_string()))?;
+
+        read_limited(entry)
+    } else if bytes.starts_with(GZIP_MAGIC) {
+        read_limited(GzDecoder::new(bytes))
+    } else {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Reads at most `MAX_FILE_SIZE` bytes, so that small archives can't expand
+/// into huge files.
+fn read_limited(reader: impl Read) -> Result<Vec<u8>, UploadError> {
+    let mut buf = Vec::new();
+
+    reader
+        .take(*MAX_FILE_SIZE as u64 + 1)
+        .read_to_end(&mut buf)
+        .map_err(|e| UploadError::InvalidArchive(e.to_string()))?;
+
+    if buf.len() > *MAX_FILE_SIZE {
+        return Err(UploadError::TooLarge);
+    }
+
+    Ok(buf)
+}
+
+/// Picks the encoding from the BOM, then from the XML declaration. Files that
+/// declare nothing and aren't valid UTF-8 are read as Windows-1252, which is
+/// what OrarioFacile and Excel use on Windows.
+fn decode_text(bytes: &[u8]) -> String {
+    let encoding = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| declared_encoding(bytes))
+        .unwrap_or_else(|| {
+            if std::str::from_utf8(bytes).is_ok() {
+                UTF_8
+            } else {
+                WINDOWS_1252
+            }
+        });
+
+    // decode() also strips the BOM
+    let (text, _, _) = encoding.decode(bytes);
+
+    text.into_owned()
+}
+
+fn declared_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    let end = bytes.iter().position(|&b| b == b'>')?;
+    let declaration = std::str::from_utf8(&bytes[..end]).ok()?;
+
+    if !declaration.starts_with("<?xml") {
+        return None;
+    }
+
+    let (_, rest) = declaration.split_once("encoding")?;
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let label = rest[1..].split(quote).next()?;
+
+    Encoding::for_label(label.as_bytes())
+}
@@ -1,19 +1,95 @@
-use ahash::HashSet;
+use ahash::{AHashMap, HashSet};
 use chrono::{Duration, NaiveTime, TimeDelta};
 use color_eyre::{Report, Result, eyre::eyre};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use sqlx::{PgPool, Postgres, QueryBuilder, Transaction};
+use thiserror::Error;
+use tracing::{error, info, warn};
 use utoipa::ToSchema;
 
 use crate::{
-    types::{Availability, AvailabilityType, IsoDow, Lesson},
-    web::endpoints::protected::import::post::{ImportFileMeta, ImportMode},
+    types::{Availability, AvailabilityType, ImportStatus, IsoDow, Lesson, Week},
+    web::endpoints::protected::import::{
+        carry_over::{CarryOverReport, carry_over_absences},
+        contents::ImportContents,
+        overlap::overlapping_imports,
+        post::{
+            ImportFileMeta, ImportMode, ImportOutcome, ImportQueued,
+            decode::UploadError,
+            parser::{AVAILABILITY_SUBJECTS, DISPOSITION_ROOM, format_duration},
+            preview::ImportPreview,
+            validation::{ValidationReport, validate},
+        },
+    },
 };
 
-#[derive(Debug, Deserialize, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename = "dataroot")]
 pub struct ScheduleFile {
     #[serde(rename = "LESSON")]
-    lessons: Vec<RawLesson>,
+    pub(super) lessons: Vec<RawLesson>,
+}
+
+impl ScheduleFile {
+    /// Rebuilds the file of an import, availabilities included, so that
+    /// importing it again gives the same contents.
+    pub fn from_contents(contents: ImportContents) -> Self {
+        let disposition_room = contents
+            .rooms
+            .iter()
+            .find(|room| room.starts_with(DISPOSITION_ROOM))
+            .cloned()
+            .unwrap_or_else(|| DISPOSITION_ROOM.to_owned());
+
+        let non_empty = |values: Vec<String>| (!values.is_empty()).then_some(values);
+        let week = |week: Option<Week>| {
+            week.map(|w| match w {
+                Week::A => "A".to_owned(),
+                Week::B => "B".to_owned(),
+            })
+        };
+
+        let lessons = contents.lessons.into_iter().map(|lesson| RawLesson {
+            duration: Some(format_duration(lesson.duration_minutes.into())),
+            subject: lesson.subject,
+            teacher: non_empty(lesson.teachers),
+            group: non_empty(lesson.groups),
+            room: non_empty(lesson.rooms),
+            week: week(lesson.week),
+            ita_day: Some(lesson.day.into()),
+            time: Some(lesson.time),
+            ..Default::default()
+        });
+
+        // Availabilities imported before their room was stored get the first
+        // DISPOSIZIONE# room of the import
+        let availabilities = contents.availabilities.into_iter().map(|availability| RawLesson {
+            duration: Some(format_duration(availability.duration_minutes.into())),
+            subject: Some(availability.availability_type.as_subject().to_owned()),
+            teacher: Some(vec![availability.teacher]),
+            room: Some(vec![availability.room.unwrap_or_else(|| disposition_room.clone())]),
+            week: week(availability.week),
+            ita_day: Some(availability.day.into()),
+            time: Some(availability.time),
+            ..Default::default()
+        });
+
+        Self {
+            lessons: lessons.chain(availabilities).collect(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("The schedule file is not valid")]
+    Invalid(ValidationReport),
+    #[error(transparent)]
+    File(#[from] UploadError),
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Other(#[from] Report),
 }
 
 /// A lesson in the schedule.
@@ -29,28 +105,37 @@ pub struct ScheduleFile {
 //   <DAY>LUN</DAY>
 //   <TIME>8:00</TIME>
 // </LESSON>
-#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub struct RawLesson {
-    duration: Option<String>,
-    subject: Option<String>,
-    #[serde(rename = "SITE")]
-    _site: Option<String>,
-    #[serde(rename = "MODULE")]
-    _module: Option<String>,
-    teacher: Option<Vec<String>>,
-    group: Option<Vec<String>>,
-    room: Option<Vec<String>>,
-    #[serde(rename = "WEEK")]
-    _week: Option<String>,
-    #[serde(rename = "DAY")]
-    ita_day: Option<ItaDay>,
-    time: Option<NaiveTime>,
-}
-
-#[derive(Debug, Clone, Deserialize, ToSchema)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) duration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) subject: Option<String>,
+    #[serde(rename = "SITE", skip_serializing_if = "Option::is_none")]
+    pub(super) _site: Option<String>,
+    #[serde(rename = "MODULE", skip_serializing_if = "Option::is_none")]
+    pub(super) _module: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) teacher: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) group: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) room: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) week: Option<String>,
+    #[serde(rename = "DAY", skip_serializing_if = "Option::is_none")]
+    pub(super) ita_day: Option<ItaDay>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_time"
+    )]
+    pub(super) time: Option<NaiveTime>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "UPPERCASE")]
-enum ItaDay {
+pub(super) enum ItaDay {
     Lun = 1,
     Mar = 2,
     Mer = 3,
@@ -60,6 +145,31 @@ enum ItaDay {
     Dom = 7,
 }
 
+/// Writes times as OrarioFacile does, e.g. 8:00
+fn serialize_time<S: Serializer>(
+    time: &Option<NaiveTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serializer.serialize_str(&time.format("%-H:%M").to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl From<IsoDow> for ItaDay {
+    fn from(value: IsoDow) -> Self {
+        match value {
+            IsoDow::Mon => Self::Lun,
+            IsoDow::Tue => Self::Mar,
+            IsoDow::Wed => Self::Mer,
+            IsoDow::Thu => Self::Gio,
+            IsoDow::Fri => Self::Ven,
+            IsoDow::Sat => Self::Sab,
+            IsoDow::Sun => Self::Dom,
+        }
+    }
+}
+
 impl TryFrom<ItaDay> for IsoDow {
     type Error = Report;
 
@@ -76,6 +186,27 @@ impl TryFrom<ItaDay> for IsoDow {
     }
 }
 
+/// Accepts LUN..DOM, the full names and 1..7, as found in CSV and Untis files
+impl TryFrom<&str> for ItaDay {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        // Full names share the first three letters with the abbreviations
+        let day = value.trim().to_uppercase();
+
+        match day.get(..3).unwrap_or(&day) {
+            "LUN" | "1" => Ok(Self::Lun),
+            "MAR" | "2" => Ok(Self::Mar),
+            "MER" | "3" => Ok(Self::Mer),
+            "GIO" | "4" => Ok(Self::Gio),
+            "VEN" | "5" => Ok(Self::Ven),
+            "SAB" | "6" => Ok(Self::Sab),
+            "DOM" | "7" => Ok(Self::Dom),
+            _ => Err(format!("Invalid day: {value}")),
+        }
+    }
+}
+
 // subject can be DISPO or RECUPERO_ORARIO
 impl TryFrom<&str> for AvailabilityType {
     type Error = Report;
@@ -92,17 +223,74 @@ impl TryFrom<&str> for AvailabilityType {
     }
 }
 
+impl AvailabilityType {
+    /// The subject of the lessons that mark this availability
+    fn as_subject(&self) -> &'static str {
+        match self {
+            Self::Availability => "DISPO",
+            Self::RecoveryHours => "RECUPERO_ORARIO",
+        }
+    }
+}
+
+// week can be A, B or empty if the lesson takes place every week
+impl TryFrom<&str> for Week {
+    type Error = Report;
+
+    fn try_from(value: &str) -> Result<Self> {
+        match value {
+            "A" => Ok(Self::A),
+            "B" => Ok(Self::B),
+            _ => Err(eyre!("Invalid week: {}", value)),
+        }
+    }
+}
+
+impl RawLesson {
+    /// Whether the subject marks an availability slot rather than a lesson
+    pub(super) fn is_availability(&self) -> bool {
+        self.subject
+            .as_deref()
+            .is_some_and(|subject| AVAILABILITY_SUBJECTS.contains(&subject))
+    }
+
+    /// Whether the lesson takes place in a DISPOSIZIONE# room, i.e. it is an
+    /// availability slot of its teacher
+    pub(super) fn is_disposition(&self) -> bool {
+        self.room
+            .as_ref()
+            .is_some_and(|rooms| rooms.iter().any(|room| room.starts_with(DISPOSITION_ROOM)))
+    }
+
+    pub(super) fn week(&self) -> Result<Option<Week>> {
+        self.week
+            .as_deref()
+            .map(str::trim)
+            .filter(|w| !w.is_empty())
+            .map(Week::try_from)
+            .transpose()
+    }
+}
+
 impl TryFrom<RawLesson> for Availability {
     type Error = Report;
 
     fn try_from(raw: RawLesson) -> Result<Self> {
+        let week = raw.week()?;
         let availability_type = raw.subject.map(|s| s.as_str().try_into()).transpose()?;
+        let duration = raw.duration.as_deref().map(parse_duration).transpose()?;
+        let room = raw
+            .room
+            .and_then(|rooms| rooms.into_iter().find(|r| r.starts_with(DISPOSITION_ROOM)));
 
         Ok(Self {
             teacher: raw.teacher,
             day: raw.ita_day.map(|d| d.try_into()).transpose()?,
             time: raw.time,
             availability_type,
+            week,
+            room,
+            duration,
         })
     }
 }
@@ -111,83 +299,269 @@ impl TryFrom<RawLesson> for Lesson {
     type Error = Report;
 
     fn try_from(raw: RawLesson) -> Result<Self> {
-        let duration: Option<TimeDelta> = raw
-            .duration
-            .as_ref()
-            .map(|d| {
-                let parts: Vec<&str> = d.split(':').collect();
-                if parts.len() != 2 {
-                    return Err(eyre!("Invalid duration format: {}", d));
-                }
-                let hours: i64 = parts[0]
-                    .parse()
-                    .map_err(|_| eyre!("Invalid hours in duration: {}", d))?;
-                let minutes: i64 = parts[1]
-                    .parse()
-                    .map_err(|_| eyre!("Invalid minutes in duration: {}", d))?;
-                Ok(Duration::hours(hours) + Duration::minutes(minutes))
-            })
-            .transpose()?;
+        let week = raw.week()?;
+        let duration: Option<TimeDelta> = raw.duration.as_deref().map(parse_duration).transpose()?;
 
         Ok(Self {
             duration,
-            // take the first teacher if any
-            teacher: raw.teacher.and_then(|t| t.into_iter().next()),
+            teachers: raw.teacher.unwrap_or_default(),
             day: raw.ita_day.map(|d| d.try_into()).transpose()?,
             time: raw.time,
-            // take the first group if any
-            group: raw.group.and_then(|g| g.into_iter().next()),
-            // take the first room if any
-            room: raw.room.and_then(|r| r.into_iter().next()),
+            groups: raw.group.unwrap_or_default(),
+            rooms: raw.room.unwrap_or_default(),
+            subject: raw.subject,
+            week,
         })
     }
 }
 
+/// Parses a duration in the H:MM format, e.g., 1:00
+pub(super) fn parse_duration(d: &str) -> Result<TimeDelta> {
+    let parts: Vec<&str> = d.split(':').collect();
+    if parts.len() != 2 {
+        return Err(eyre!("Invalid duration format: {}", d));
+    }
+    let hours: i64 = parts[0]
+        .parse()
+        .map_err(|_| eyre!("Invalid hours in duration: {}", d))?;
+    let minutes: i64 = parts[1]
+        .parse()
+        .map_err(|_| eyre!("Invalid minutes in duration: {}", d))?;
+    Ok(Duration::hours(hours) + Duration::minutes(minutes))
+}
+
+/// Validates and imports the file. Dry runs are rolled back and return a
+/// preview of what would have been created.
 pub async fn import_file(
     db: &PgPool,
     meta: ImportFileMeta,
     schedule_file: ScheduleFile,
     user_id: i32,
-) -> Result<()> {
-    let raw_lessons: Vec<RawLesson> = schedule_file.lessons;
+) -> Result<ImportOutcome, ImportError> {
+    let report = validate(&schedule_file, meta.week_a_start);
+
+    if report.has_errors() {
+        return Err(ImportError::Invalid(report));
+    }
 
     let mut txn = db.begin().await?;
 
-    let import_id = create_import_record(&meta, user_id, &mut txn).await?;
+    let import_id = create_import_record(&meta, ImportStatus::Done, user_id, &mut txn).await?;
+
+    let carry_over = fill_import(schedule_file.lessons, import_id, user_id, &mut txn).await?;
+
+    let overlaps = overlapping_imports(&mut txn, import_id, user_id).await?;
+
+    let outcome = match meta.mode {
+        ImportMode::Write => {
+            txn.commit().await?;
+
+            ImportOutcome {
+                import_id: Some(import_id),
+                report,
+                carry_over,
+                preview: None,
+                overlaps,
+            }
+        }
+        ImportMode::DryRun => {
+            let preview = ImportPreview::build(&mut txn, import_id, user_id, &meta).await?;
+
+            txn.rollback().await?;
+
+            ImportOutcome {
+                import_id: None,
+                report,
+                carry_over,
+                preview: Some(preview),
+                overlaps,
+            }
+        }
+    };
+
+    Ok(outcome)
+}
+
+/// Validates the file and creates a pending import, which is filled by a
+/// background job. Its progress is reported by `GET /import/{id}/status`.
+pub async fn queue_import(
+    db: &PgPool,
+    meta: ImportFileMeta,
+    schedule_file: ScheduleFile,
+    user_id: i32,
+) -> Result<ImportQueued, ImportError> {
+    let report = validate(&schedule_file, meta.week_a_start);
+
+    if report.has_errors() {
+        return Err(ImportError::Invalid(report));
+    }
+
+    let mut txn = db.begin().await?;
+
+    let import_id = create_import_record(&meta, ImportStatus::Pending, user_id, &mut txn).await?;
+
+    let overlaps = overlapping_imports(&mut txn, import_id, user_id).await?;
+
+    txn.commit().await?;
+
+    tokio::spawn(run_import(
+        db.clone(),
+        import_id,
+        user_id,
+        schedule_file.lessons,
+        report,
+    ));
+
+    Ok(ImportQueued {
+        import_id,
+        overlaps,
+    })
+}
+
+/// The background job of `queue_import`. The lessons are written together
+/// with the Done status, so a pending or failed import never has contents.
+async fn run_import(
+    db: PgPool,
+    import_id: i32,
+    user_id: i32,
+    raw_lessons: Vec<RawLesson>,
+    report: ValidationReport,
+) {
+    info!("Processing import {}", import_id);
+
+    let res: Result<()> = async {
+        sqlx::query!(
+            r#"
+            UPDATE import SET status = 'Running' WHERE id = $1
+            "#,
+            import_id,
+        )
+        .execute(&db)
+        .await?;
+
+        let mut txn = db.begin().await?;
+
+        let carry_over = fill_import(raw_lessons, import_id, user_id, &mut txn).await?;
+
+        let overlaps = overlapping_imports(&mut txn, import_id, user_id).await?;
 
-    import_rooms(&raw_lessons, import_id, &mut txn).await?;
+        let outcome = ImportOutcome {
+            import_id: Some(import_id),
+            report,
+            carry_over,
+            preview: None,
+            overlaps,
+        };
 
-    import_groups(&raw_lessons, import_id, &mut txn).await?;
+        sqlx::query!(
+            r#"
+            UPDATE import
+            SET status      = 'Done',
+                outcome     = $2::text::jsonb,
+                finished_ts = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            import_id,
+            sonic_rs::to_string(&outcome)?,
+        )
+        .execute(&mut *txn)
+        .await?;
 
-    import_teachers(&raw_lessons, import_id, &mut txn).await?;
+        txn.commit().await?;
 
-    import_availabilities(raw_lessons.clone(), import_id, &mut txn).await?;
+        Ok(())
+    }
+    .await;
 
-    import_lessons(raw_lessons, import_id, &mut txn).await?;
+    match res {
+        Ok(()) => info!("Import {} done", import_id),
+        Err(e) => {
+            error!("Error importing file {}: {:?}", import_id, e);
 
-    match meta.mode {
-        ImportMode::Write => txn.commit().await,
-        ImportMode::DryRun => txn.rollback().await,
-    }?;
+            if let Err(e) = sqlx::query!(
+                r#"
+                UPDATE import
+                SET status      = 'Failed',
+                    error       = $2,
+                    finished_ts = CURRENT_TIMESTAMP
+                WHERE id = $1
+                "#,
+                import_id,
+                e.to_string(),
+            )
+            .execute(&db)
+            .await
+            {
+                error!("Error marking import {} as failed: {}", import_id, e);
+            }
+        }
+    }
+}
+
+/// Marks as failed the imports left pending or running by a previous run of
+/// the server, their jobs are gone.
+pub async fn fail_interrupted_imports(db: &PgPool) -> Result<()> {
+    let interrupted = sqlx::query!(
+        r#"
+        UPDATE import
+        SET status      = 'Failed',
+            error       = 'Interrupted by a restart of the server',
+            finished_ts = CURRENT_TIMESTAMP
+        WHERE status IN ('Pending', 'Running')
+        "#,
+    )
+    .execute(db)
+    .await?;
+
+    if interrupted.rows_affected() > 0 {
+        warn!("Marked {} interrupted imports as failed", interrupted.rows_affected());
+    }
 
     Ok(())
 }
 
+/// Writes the contents of the file to the import and carries over the
+/// absences of the imports it replaces.
+async fn fill_import(
+    raw_lessons: Vec<RawLesson>,
+    import_id: i32,
+    user_id: i32,
+    txn: &mut Transaction<'_, Postgres>,
+) -> Result<CarryOverReport> {
+    let ids = ImportIds {
+        rooms: import_rooms(&raw_lessons, import_id, txn).await?,
+        groups: import_groups(&raw_lessons, import_id, txn).await?,
+        teachers: import_teachers(&raw_lessons, import_id, txn).await?,
+        subjects: import_subjects(&raw_lessons, import_id, txn).await?,
+    };
+
+    link_teachers_to_staff(import_id, txn).await?;
+
+    import_availabilities(raw_lessons.clone(), &ids, txn).await?;
+
+    import_lessons(raw_lessons, &ids, import_id, txn).await?;
+
+    carry_over_absences(txn, import_id, user_id).await
+}
+
 async fn create_import_record(
     import_file_meta: &ImportFileMeta,
+    status: ImportStatus,
     user_id: i32,
     txn: &mut Transaction<'_, Postgres>,
 ) -> Result<i32> {
     let import_id = sqlx::query!(
         r#"
-        INSERT INTO "import" (user_id, file_name, begin_ts, end_ts)
-        VALUES ($1, $2, $3, $4)
+        INSERT INTO "import" (user_id, file_name, begin_ts, end_ts, week_a_start, status)
+        VALUES ($1, $2, $3, $4, DATE_TRUNC('week', $5::date)::date, $6)
         RETURNING id
         "#,
         user_id,
         import_file_meta.file_name,
         import_file_meta.begin_ts,
-        import_file_meta.end_ts
+        import_file_meta.end_ts,
+        import_file_meta.week_a_start,
+        status as ImportStatus,
     )
     .fetch_one(&mut **txn)
     .await?;
@@ -195,202 +569,754 @@ async fn create_import_record(
     Ok(import_id.id)
 }
 
-async fn import_rooms(
-    raw_lessons: &[RawLesson],
+/// Ids of the rows created by the import, by name
+type IdsByName = AHashMap<String, i32>;
+
+#[derive(Debug, Default)]
+struct ImportIds {
+    rooms: IdsByName,
+    groups: IdsByName,
+    teachers: IdsByName,
+    subjects: IdsByName,
+}
+
+fn id_of(ids: &IdsByName, name: &str) -> Result<i32> {
+    ids.get(name)
+        .copied()
+        .ok_or_else(|| eyre!("{} was not imported", name))
+}
+
+/// Inserts the names in `table` and returns their ids.
+async fn insert_names(
+    names: HashSet<&String>,
+    table: &str,
+    column: &str,
     import_id: i32,
     txn: &mut Transaction<'_, Postgres>,
-) -> Result<()> {
-    let all_rooms: Vec<&String> = raw_lessons
-        .iter()
-        .filter_map(|lesson| lesson.room.as_ref())
-        .flatten()
-        .collect::<HashSet<&String>>()
-        .into_iter()
-        .collect();
+) -> Result<IdsByName> {
+    if names.is_empty() {
+        return Ok(IdsByName::default());
+    }
 
-    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
+    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(format!(
         r#"
-        INSERT INTO "room" (name, import_id)
-        "#,
-    );
+        INSERT INTO "{table}" ({column}, import_id)
+        "#
+    ));
 
-    query_builder.push_values(all_rooms, |mut b, room| {
-        b.push_bind(room);
+    query_builder.push_values(names, |mut b, name| {
+        b.push_bind(name);
         b.push_bind(import_id);
     });
 
-    query_builder.build().execute(&mut **txn).await?;
+    query_builder.push(format!(" RETURNING id, {column}"));
+
+    let rows: Vec<(i32, String)> = query_builder.build_query_as().fetch_all(&mut **txn).await?;
+
+    Ok(rows.into_iter().map(|(id, name)| (name, id)).collect())
+}
+
+/// Links lessons to teachers, groups or rooms through `table`.
+async fn insert_links(
+    links: Vec<(i32, i32)>,
+    table: &str,
+    column: &str,
+    txn: &mut Transaction<'_, Postgres>,
+) -> Result<()> {
+    let (lesson_ids, ids): (Vec<i32>, Vec<i32>) = links.into_iter().unzip();
+
+    sqlx::query(&format!(
+        r#"
+        INSERT INTO {table} (lesson_id, {column})
+        SELECT * FROM UNNEST($1::integer[], $2::integer[])
+        ON CONFLICT DO NOTHING
+        "#
+    ))
+    .bind(lesson_ids)
+    .bind(ids)
+    .execute(&mut **txn)
+    .await?;
 
     Ok(())
 }
 
+async fn import_rooms(
+    raw_lessons: &[RawLesson],
+    import_id: i32,
+    txn: &mut Transaction<'_, Postgres>,
+) -> Result<IdsByName> {
+    let all_rooms: HashSet<&String> = raw_lessons
+        .iter()
+        .filter_map(|lesson| lesson.room.as_ref())
+        .flatten()
+        .collect();
+
+    insert_names(all_rooms, "room", "name", import_id, txn).await
+}
+
 async fn import_groups(
     raw_lessons: &[RawLesson],
     import_id: i32,
     txn: &mut Transaction<'_, Postgres>,
-) -> Result<()> {
-    let all_groups: Vec<&String> = raw_lessons
+) -> Result<IdsByName> {
+    let all_groups: HashSet<&String> = raw_lessons
         .iter()
         .filter_map(|lesson| lesson.group.as_ref())
         .flatten()
-        .collect::<HashSet<&String>>()
-        .into_iter()
         .collect();
 
-    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
-        r#"
-        INSERT INTO "group" (name, import_id)
-        "#,
-    );
+    insert_names(all_groups, "group", "name", import_id, txn).await
+}
 
-    query_builder.push_values(all_groups, |mut b, group| {
-        b.push_bind(group);
-        b.push_bind(import_id);
-    });
+async fn import_subjects(
+    raw_lessons: &[RawLesson],
+    import_id: i32,
+    txn: &mut Transaction<'_, Postgres>,
+) -> Result<IdsByName> {
+    let all_subjects: HashSet<&String> = raw_lessons
+        .iter()
+        .filter(|lesson| !lesson.is_availability())
+        .filter_map(|lesson| lesson.subject.as_ref())
+        .collect();
+
+    insert_names(all_subjects, "subject", "name", import_id, txn).await
+}
 
-    query_builder.build().execute(&mut **txn).await?;
+async fn import_teachers(
+    raw_lessons: &[RawLesson],
+    import_id: i32,
+    txn: &mut Transaction<'_, Postgres>,
+) -> Result<IdsByName> {
+    let all_teachers: HashSet<&String> = raw_lessons
+        .iter()
+        .filter_map(|lesson| lesson.teacher.as_ref())
+        .flatten()
+        .collect();
 
-    Ok(())
+    insert_names(all_teachers, "teacher", "full_name", import_id, txn).await
 }
 
 async fn import_lessons(
     raw_lessons: Vec<RawLesson>,
+    ids: &ImportIds,
     import_id: i32,
     txn: &mut Transaction<'_, Postgres>,
 ) -> Result<()> {
     let lessons = raw_lessons
         .into_iter()
-        .filter(|lesson| {
-            if let Some(subject) = &lesson.subject {
-                subject != "DISPO" && subject != "RECUPERO_ORARIO"
-            } else {
-                true
-            }
-        })
+        .filter(|lesson| !lesson.is_availability())
         .map(|lesson| lesson.try_into())
         .collect::<Result<Vec<Lesson>>>()?;
 
-    for lesson in lessons {
+    if lessons.is_empty() {
+        return Ok(());
+    }
+
+    // Reserve the ids up front, so that the join tables can be filled
+    // without relying on the order of RETURNING
+    let lesson_ids = sqlx::query_scalar!(
+        r#"
+        SELECT NEXTVAL('lesson_id_seq')::integer AS "id!"
+        FROM GENERATE_SERIES(1, $1)
+        "#,
+        lessons.len() as i32
+    )
+    .fetch_all(&mut **txn)
+    .await?;
+
+    let mut days = Vec::with_capacity(lessons.len());
+    let mut times = Vec::with_capacity(lessons.len());
+    let mut durations = Vec::with_capacity(lessons.len());
+    let mut weeks = Vec::with_capacity(lessons.len());
+    let mut subject_ids = Vec::with_capacity(lessons.len());
+
+    let mut teacher_links = Vec::new();
+    let mut group_links = Vec::new();
+    let mut room_links = Vec::new();
+
+    for (&lesson_id, lesson) in lesson_ids.iter().zip(&lessons) {
         let day = lesson
             .day
-            .as_ref()
             .ok_or_else(|| eyre!("Lesson doesn't have a day: {:?}", lesson))?;
+        let time = lesson
+            .time
+            .ok_or_else(|| eyre!("Lesson doesn't have a time: {:?}", lesson))?;
 
-        sqlx::query!(
-            r#"
-            INSERT INTO "lesson" (day, time, duration, room_id, group_id, teacher_id)
-            SELECT
-              $1::smallint::isodow,
-              $2,
-              $3,
-              (SELECT id FROM room WHERE name = $4 AND import_id = $7),
-              (SELECT id FROM "group" WHERE name = $5 AND import_id = $7),
-              (SELECT id FROM teacher WHERE full_name = $6 AND import_id = $7)
-            "#,
-            day.iso_dow(),
-            lesson.time as Option<NaiveTime>,
-            lesson.duration as Option<TimeDelta>,
-            lesson.room.as_deref(),
-            lesson.group.as_deref(),
-            lesson.teacher.as_deref(),
-            import_id
-        )
-        .execute(&mut **txn)
-        .await?;
+        days.push(day.iso_dow());
+        times.push(time);
+        durations.push(lesson.duration);
+        weeks.push(lesson.week);
+        subject_ids.push(
+            lesson
+                .subject
+                .as_deref()
+                .map(|s| id_of(&ids.subjects, s))
+                .transpose()?,
+        );
+
+        for teacher in &lesson.teachers {
+            teacher_links.push((lesson_id, id_of(&ids.teachers, teacher)?));
+        }
+        for group in &lesson.groups {
+            group_links.push((lesson_id, id_of(&ids.groups, group)?));
+        }
+        for room in &lesson.rooms {
+            room_links.push((lesson_id, id_of(&ids.rooms, room)?));
+        }
     }
 
+    sqlx::query(
+        r#"
+        INSERT INTO "lesson" (id, import_id, day, time, duration, week, subject_id)
+        SELECT l.id,
+               $1,
+               l.day::isodow,
+               l.time,
+               COALESCE(l.duration, INTERVAL '1 hour'),
+               l.week,
+               l.subject_id
+        FROM UNNEST($2::integer[], $3::smallint[], $4::time[], $5::interval[],
+                    $6::week_label[], $7::integer[])
+                 AS l(id, day, time, duration, week, subject_id)
+        "#,
+    )
+    .bind(import_id)
+    .bind(lesson_ids)
+    .bind(days)
+    .bind(times)
+    .bind(durations)
+    .bind(weeks)
+    .bind(subject_ids)
+    .execute(&mut **txn)
+    .await?;
+
+    insert_links(teacher_links, "lesson_teacher", "teacher_id", txn).await?;
+    insert_links(group_links, "lesson_group", "group_id", txn).await?;
+    insert_links(room_links, "lesson_room", "room_id", txn).await?;
+
     Ok(())
 }
 
 async fn import_availabilities(
     raw_lessons: Vec<RawLesson>,
-    import_id: i32,
+    ids: &ImportIds,
     txn: &mut Transaction<'_, Postgres>,
 ) -> Result<()> {
     // Filter for lessons that have any room that starts with DISPOSIZIONE#
     let lessons = raw_lessons
         .into_iter()
-        .filter(|lesson| {
-            if let Some(rooms) = &lesson.room {
-                rooms.iter().any(|room| room.starts_with("DISPOSIZIONE#"))
-            } else {
-                false
-            }
-        })
+        .filter(|lesson| lesson.is_disposition())
         .map(|lesson| lesson.try_into())
         .collect::<Result<Vec<Availability>>>()?;
 
-    // assert that every lesson has exactly one teacher
-    for lesson in &lessons {
-        assert_eq!(
-            lesson.teacher.as_ref().map_or(0, |v| v.len()),
-            1,
-            "Lesson doesn't have exactly one teacher: {:?}",
-            lesson
-        );
-    }
+    let mut teacher_ids = Vec::with_capacity(lessons.len());
+    let mut days = Vec::with_capacity(lessons.len());
+    let mut times = Vec::with_capacity(lessons.len());
+    let mut availability_types = Vec::with_capacity(lessons.len());
+    let mut weeks = Vec::with_capacity(lessons.len());
+    let mut room_ids = Vec::with_capacity(lessons.len());
+    let mut durations = Vec::with_capacity(lessons.len());
 
-    for lesson in &lessons {
+    for lesson in lessons {
         let day = lesson
             .day
-            .as_ref()
             .ok_or_else(|| eyre!("Lesson doesn't have a day: {:?}", lesson))?;
+        let time = lesson
+            .time
+            .ok_or_else(|| eyre!("Lesson doesn't have a time: {:?}", lesson))?;
         let teacher = lesson
             .teacher
             .as_ref()
             .and_then(|t| t.first())
             .ok_or_else(|| eyre!("Lesson doesn't have a teacher: {:?}", lesson))?;
+        let teacher_id = id_of(&ids.teachers, teacher)?;
         let availability_type = lesson
             .availability_type
-            .as_ref()
+            .clone()
             .ok_or_else(|| eyre!("Lesson doesn't have an availability type: {:?}", lesson))?;
 
+        teacher_ids.push(teacher_id);
+        days.push(day.iso_dow());
+        times.push(time);
+        availability_types.push(availability_type);
+        weeks.push(lesson.week);
+        room_ids.push(
+            lesson
+                .room
+                .as_deref()
+                .map(|r| id_of(&ids.rooms, r))
+                .transpose()?,
+        );
+        durations.push(lesson.duration);
+    }
+
+    if teacher_ids.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO "availability" (teacher_id, day, time, availability_type, week, room_id,
+                                    duration)
+        SELECT a.teacher_id,
+               a.day::isodow,
+               a.time,
+               a.availability_type,
+               a.week,
+               a.room_id,
+               COALESCE(a.duration, INTERVAL '1 hour')
+        FROM UNNEST($1::integer[], $2::smallint[], $3::time[], $4::availability_type[],
+                    $5::week_label[], $6::integer[], $7::interval[])
+                 AS a(teacher_id, day, time, availability_type, week, room_id, duration)
+        "#,
+    )
+    .bind(teacher_ids)
+    .bind(days)
+    .bind(times)
+    .bind(availability_types)
+    .bind(weeks)
+    .bind(room_ids)
+    .bind(durations)
+    .execute(&mut **txn)
+    .await?;
+
+    Ok(())
+}
+
+async fn link_teachers_to_staff(import_id: i32, txn: &mut Transaction<'_, Postgres>) -> Result<()> {
+    sqlx::query!(
+        r#"
+        SELECT link_teachers_to_staff($1) AS "linked!"
+        "#,
+        import_id
+    )
+    .fetch_one(&mut **txn)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use chrono::{Datelike, Days, Local, NaiveDate};
+
+    use super::*;
+    use crate::web::endpoints::protected::import::post::ImportMode;
+
+    const GROUPS: usize = 50;
+    const LESSONS_PER_GROUP: usize = 40;
+    const TEACHERS: usize = 120;
+    const ROOMS: usize = 50;
+    const SUBJECTS: usize = 10;
+
+    // Lessons of several teachers, groups and rooms, alternating weeks and
+    // availabilities in two DISPOSIZIONE# rooms, one of them of 2 hours
+    const SCHEDULE: &str = r#"<dataroot>
+        <LESSON>
+            <DURATION>2:00</DURATION><SUBJECT>INFORMATICA</SUBJECT>
+            <TEACHER>ROSSI MARIO</TEACHER><TEACHER>BIANCHI LUCA</TEACHER>
+            <GROUP>5^A-IA</GROUP><GROUP>5^B-IA</GROUP><ROOM>07-TW</ROOM>
+            <DAY>LUN</DAY><TIME>8:00</TIME>
+        </LESSON>
+        <LESSON>
+            <DURATION>1:00</DURATION><SUBJECT>INGLESE</SUBJECT>
+            <TEACHER>VERDI GIULIA</TEACHER><GROUP>5^A-IA</GROUP><ROOM>12</ROOM>
+            <WEEK>A</WEEK><DAY>MAR</DAY><TIME>9:00</TIME>
+        </LESSON>
+        <LESSON>
+            <DURATION>1:00</DURATION><SUBJECT>STORIA</SUBJECT>
+            <TEACHER>VERDI GIULIA</TEACHER><GROUP>5^A-IA</GROUP><ROOM>12</ROOM>
+            <WEEK>B</WEEK><DAY>MAR</DAY><TIME>9:00</TIME>
+        </LESSON>
+        <LESSON>
+            <DURATION>1:00</DURATION><SUBJECT>DISPO</SUBJECT>
+            <TEACHER>ROSSI MARIO</TEACHER><ROOM>DISPOSIZIONE#1</ROOM>
+            <DAY>MER</DAY><TIME>10:00</TIME>
+        </LESSON>
+        <LESSON>
+            <DURATION>2:00</DURATION><SUBJECT>RECUPERO_ORARIO</SUBJECT>
+            <TEACHER>VERDI GIULIA</TEACHER><ROOM>DISPOSIZIONE#2</ROOM>
+            <WEEK>B</WEEK><DAY>GIO</DAY><TIME>11:00</TIME>
+        </LESSON>
+    </dataroot>"#;
+
+    async fn create_user(db: &PgPool) -> Result<i32> {
+        Ok(sqlx::query_scalar!(
+            r#"
+            INSERT INTO "user" (username, password) VALUES ('test', '') RETURNING id
+            "#
+        )
+        .fetch_one(db)
+        .await?)
+    }
+
+    /// A one hour lesson, an availability if its room is a DISPOSIZIONE
+    fn raw_lesson(
+        subject: &str,
+        teacher: &str,
+        group: Option<&str>,
+        room: &str,
+        day: ItaDay,
+        time: NaiveTime,
+    ) -> RawLesson {
+        RawLesson {
+            duration: Some("1:00".to_owned()),
+            subject: Some(subject.to_owned()),
+            _site: None,
+            _module: None,
+            teacher: Some(vec![teacher.to_owned()]),
+            group: group.map(|g| vec![g.to_owned()]),
+            room: Some(vec![room.to_owned()]),
+            week: None,
+            ita_day: Some(day),
+            time: Some(time),
+        }
+    }
+
+    /// ROSSI teaches at 8:00 and BIANCHI is available at 8:00, every day
+    fn every_day_schedule() -> ScheduleFile {
+        let days = [
+            ItaDay::Lun,
+            ItaDay::Mar,
+            ItaDay::Mer,
+            ItaDay::Gio,
+            ItaDay::Ven,
+            ItaDay::Sab,
+            ItaDay::Dom,
+        ];
+        let time = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+
+        let lessons = days.into_iter().flat_map(|day| {
+            [
+                raw_lesson("INFORMATICA", "ROSSI MARIO", Some("5^A-IA"), "07-TW", day.clone(), time),
+                raw_lesson("DISPO", "BIANCHI LUCA", None, DISPOSITION_ROOM, day, time),
+            ]
+        });
+
+        ScheduleFile {
+            lessons: lessons.collect(),
+        }
+    }
+
+    /// Imports the schedule for the next 30 days
+    async fn import_next_month(db: &PgPool, user_id: i32) -> Result<i32> {
+        let today = Local::now().date_naive();
+        let meta = ImportFileMeta {
+            file_name: "orario.xml".to_owned(),
+            mode: ImportMode::Write,
+            begin_ts: today.and_time(NaiveTime::MIN),
+            end_ts: (today + Days::new(30)).and_hms_opt(23, 59, 59).unwrap(),
+            week_a_start: None,
+        };
+
+        let outcome = import_file(db, meta, every_day_schedule(), user_id).await?;
+
+        Ok(outcome.import_id.unwrap())
+    }
+
+    /// A school timetable of 2,000 lessons, 40 hours a week for 50 groups,
+    /// and a DISPO for every teacher
+    fn large_schedule() -> ScheduleFile {
+        let slot = |n: usize| {
+            let days = [ItaDay::Lun, ItaDay::Mar, ItaDay::Mer, ItaDay::Gio, ItaDay::Ven];
+            let time = NaiveTime::from_hms_opt(8 + (n / 5 % 8) as u32, 0, 0).unwrap();
+
+            (days[n % 5].clone(), time)
+        };
+
+        let lessons = (0..GROUPS).flat_map(|g| {
+            (0..LESSONS_PER_GROUP).map(move |l| {
+                let (day, time) = slot(l);
+                let teacher = (g * LESSONS_PER_GROUP + l) % TEACHERS;
+
+                raw_lesson(
+                    &format!("SUBJECT {}", l % SUBJECTS),
+                    &format!("TEACHER {teacher}"),
+                    Some(&format!("GROUP {g}")),
+                    &format!("ROOM {}", g % ROOMS),
+                    day,
+                    time,
+                )
+            })
+        });
+
+        let availabilities = (0..TEACHERS).map(|t| {
+            let (day, time) = slot(t);
+
+            raw_lesson("DISPO", &format!("TEACHER {t}"), None, DISPOSITION_ROOM, day, time)
+        });
+
+        ScheduleFile {
+            lessons: lessons.chain(availabilities).collect(),
+        }
+    }
+
+    fn meta() -> ImportFileMeta {
+        let date = NaiveDate::from_ymd_opt(2026, 9, 14).unwrap();
+
+        ImportFileMeta {
+            file_name: "large.xml".to_owned(),
+            mode: ImportMode::Write,
+            begin_ts: date.and_time(NaiveTime::MIN),
+            end_ts: date.and_hms_opt(23, 59, 59).unwrap(),
+            week_a_start: None,
+        }
+    }
+
+    /// Creates an import with the rooms, groups, teachers and subjects of the
+    /// schedule, ready for its lessons
+    async fn import_names(
+        txn: &mut Transaction<'_, Postgres>,
+        raw_lessons: &[RawLesson],
+        user_id: i32,
+    ) -> Result<(i32, ImportIds)> {
+        let import_id = create_import_record(&meta(), ImportStatus::Done, user_id, txn).await?;
+
+        let ids = ImportIds {
+            rooms: import_rooms(raw_lessons, import_id, txn).await?,
+            groups: import_groups(raw_lessons, import_id, txn).await?,
+            teachers: import_teachers(raw_lessons, import_id, txn).await?,
+            subjects: import_subjects(raw_lessons, import_id, txn).await?,
+        };
+
+        Ok((import_id, ids))
+    }
+
+    #[sqlx::test]
+    async fn carry_over_with_substitute(db: PgPool) -> Result<()> {
+        let user_id = create_user(&db).await?;
+
+        let old_import = import_next_month(&db, user_id).await?;
+        let date = Local::now().date_naive() + Days::new(7);
+
         sqlx::query!(
             r#"
-            INSERT INTO "availability" (day, time, availability_type, teacher_id)
-            SELECT $1::smallint::isodow,
-                   $2,
-                   $3,
-                   (SELECT id FROM teacher WHERE full_name = $4 AND import_id = $5)
+            INSERT INTO absence (absent_teacher_lesson, absent_teacher, absence_date, status,
+                                 substitute_teacher_availability)
+            SELECT lt.lesson_id, lt.teacher_id, $2, 'SubstituteFound', av.id
+            FROM lesson l
+                     JOIN lesson_teacher lt ON lt.lesson_id = l.id
+                     JOIN availability av ON av.day = l.day
+                     JOIN teacher t ON av.teacher_id = t.id AND t.import_id = l.import_id
+            WHERE l.import_id = $1
+              AND l.day = $3::smallint
+            "#,
+            old_import,
+            date,
+            date.weekday().number_from_monday() as i16,
+        )
+        .execute(&db)
+        .await?;
+
+        // The new import carries the absence over when it is created
+        let new_import = import_next_month(&db, user_id).await?;
+
+        let absence = sqlx::query!(
+            r#"
+            SELECT l.import_id      AS lesson_import,
+                   t.import_id      AS substitute_import,
+                   ab.status::text  AS "status!"
+            FROM absence ab
+                     JOIN lesson l ON ab.absent_teacher_lesson = l.id
+                     JOIN availability av ON ab.substitute_teacher_availability = av.id
+                     JOIN teacher t ON av.teacher_id = t.id
+            "#
+        )
+        .fetch_one(&db)
+        .await?;
+
+        assert_eq!(absence.lesson_import, new_import);
+        assert_eq!(absence.substitute_import, new_import);
+        assert_eq!(absence.status, "SubstituteFound");
+
+        Ok(())
+    }
+
+    /// The lesson inserts before they were batched: a statement for the
+    /// lesson and one for each of its links, names resolved by the database
+    async fn import_lessons_per_row(
+        raw_lessons: Vec<RawLesson>,
+        import_id: i32,
+        txn: &mut Transaction<'_, Postgres>,
+    ) -> Result<()> {
+        let lessons = raw_lessons
+            .into_iter()
+            .filter(|lesson| !lesson.is_availability())
+            .map(|lesson| lesson.try_into())
+            .collect::<Result<Vec<Lesson>>>()?;
+
+        for lesson in lessons {
+            let (lesson_id,): (i32,) = sqlx::query_as(
+                r#"
+                INSERT INTO "lesson" (import_id, day, time, duration, week, subject_id)
+                SELECT $1,
+                       $2::smallint::isodow,
+                       $3,
+                       COALESCE($4, INTERVAL '1 hour'),
+                       $5,
+                       (SELECT id FROM subject WHERE name = $6 AND import_id = $1)
+                RETURNING id
+                "#,
+            )
+            .bind(import_id)
+            .bind(lesson.day.map(|d| d.iso_dow()))
+            .bind(lesson.time)
+            .bind(lesson.duration)
+            .bind(lesson.week)
+            .bind(lesson.subject.as_deref())
+            .fetch_one(&mut **txn)
+            .await?;
+
+            for (names, table, column, names_table, name_column) in [
+                (&lesson.teachers, "lesson_teacher", "teacher_id", "teacher", "full_name"),
+                (&lesson.groups, "lesson_group", "group_id", "\"group\"", "name"),
+                (&lesson.rooms, "lesson_room", "room_id", "room", "name"),
+            ] {
+                sqlx::query(&format!(
+                    "INSERT INTO {table} (lesson_id, {column}) SELECT $1, id FROM {names_table} \
+                     WHERE {name_column} = ANY($2) AND import_id = $3"
+                ))
+                .bind(lesson_id)
+                .bind(names)
+                .bind(import_id)
+                .execute(&mut **txn)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Times the lessons of the schedule written both ways, each in a fresh
+    /// import
+    #[sqlx::test]
+    async fn batched_lesson_inserts_are_faster(db: PgPool) -> Result<()> {
+        let user_id = create_user(&db).await?;
+        let raw_lessons = large_schedule().lessons;
+
+        let mut txn = db.begin().await?;
+        let (import_id, ids) = import_names(&mut txn, &raw_lessons, user_id).await?;
+        let start = Instant::now();
+        import_lessons(raw_lessons.clone(), &ids, import_id, &mut txn).await?;
+        let batched = start.elapsed();
+        txn.commit().await?;
+
+        let mut txn = db.begin().await?;
+        let (import_id, _) = import_names(&mut txn, &raw_lessons, user_id).await?;
+        let start = Instant::now();
+        import_lessons_per_row(raw_lessons, import_id, &mut txn).await?;
+        let per_row = start.elapsed();
+        txn.commit().await?;
+
+        println!("2,000 lessons: batched {batched:?}, per row {per_row:?}");
+
+        // Several thousand round trips against a handful
+        assert!(batched < per_row);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn import_large_schedule(db: PgPool) -> Result<()> {
+        let user_id = create_user(&db).await?;
+
+        let outcome = import_file(&db, meta(), large_schedule(), user_id).await?;
+        let import_id = outcome.import_id.unwrap();
+
+        let counts = sqlx::query!(
+            r#"
+            SELECT (SELECT COUNT(*) FROM lesson WHERE import_id = $1)         AS "lessons!",
+                   (SELECT COUNT(*)
+                    FROM lesson_teacher lt
+                             JOIN lesson l ON lt.lesson_id = l.id
+                    WHERE l.import_id = $1)                                   AS "lesson_teachers!",
+                   (SELECT COUNT(*)
+                    FROM lesson_group lg
+                             JOIN lesson l ON lg.lesson_id = l.id
+                    WHERE l.import_id = $1)                                   AS "lesson_groups!",
+                   (SELECT COUNT(*)
+                    FROM lesson_room lr
+                             JOIN lesson l ON lr.lesson_id = l.id
+                    WHERE l.import_id = $1)                                   AS "lesson_rooms!",
+                   (SELECT COUNT(*) FROM teacher WHERE import_id = $1)        AS "teachers!",
+                   (SELECT COUNT(*) FROM room WHERE import_id = $1)           AS "rooms!",
+                   (SELECT COUNT(*) FROM "group" WHERE import_id = $1)        AS "groups!",
+                   (SELECT COUNT(*) FROM subject WHERE import_id = $1)        AS "subjects!",
+                   (SELECT COUNT(*)
+                    FROM availability av
+                             JOIN teacher t ON av.teacher_id = t.id
+                    WHERE t.import_id = $1)                                   AS "availabilities!"
             "#,
-            day.iso_dow(),
-            lesson.time as Option<NaiveTime>,
-            availability_type as &AvailabilityType,
-            teacher,
             import_id
         )
-        .execute(&mut **txn)
+        .fetch_one(&db)
         .await?;
+
+        let lessons = (GROUPS * LESSONS_PER_GROUP) as i64;
+
+        assert_eq!(counts.lessons, lessons);
+        assert_eq!(counts.lesson_teachers, lessons);
+        assert_eq!(counts.lesson_groups, lessons);
+        assert_eq!(counts.lesson_rooms, lessons);
+        assert_eq!(counts.teachers, TEACHERS as i64);
+        // The DISPOSIZIONE# room of the availabilities too
+        assert_eq!(counts.rooms, ROOMS as i64 + 1);
+        assert_eq!(counts.groups, GROUPS as i64);
+        assert_eq!(counts.subjects, SUBJECTS as i64);
+        assert_eq!(counts.availabilities, TEACHERS as i64);
+
+        Ok(())
     }
 
-    Ok(())
-}
+    /// Imports an OrarioFacile export for a week, week A
+    async fn import_xml(db: &PgPool, file_name: &str, xml: &str, user_id: i32) -> Result<i32> {
+        let date = NaiveDate::from_ymd_opt(2026, 9, 14).unwrap();
+        let meta = ImportFileMeta {
+            file_name: file_name.to_owned(),
+            mode: ImportMode::Write,
+            begin_ts: date.and_time(NaiveTime::MIN),
+            end_ts: date.and_hms_opt(23, 59, 59).unwrap(),
+            week_a_start: Some(date),
+        };
 
-async fn import_teachers(
-    raw_lessons: &[RawLesson],
-    import_id: i32,
-    txn: &mut Transaction<'_, Postgres>,
-) -> Result<()> {
-    let all_teachers: Vec<&String> = raw_lessons
-        .iter()
-        .filter_map(|lesson| lesson.teacher.as_ref())
-        .flatten()
-        .collect::<HashSet<&String>>()
-        .into_iter()
-        .collect();
+        let outcome = import_file(db, meta, quick_xml::de::from_str(xml)?, user_id).await?;
 
-    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
-        r#"
-        INSERT INTO "teacher" (full_name, import_id)
-        "#,
-    );
+        Ok(outcome.import_id.unwrap())
+    }
 
-    query_builder.push_values(all_teachers, |mut b, teacher| {
-        b.push_bind(teacher);
-        b.push_bind(import_id);
-    });
+    async fn load(db: &PgPool, import_id: i32) -> Result<ImportContents> {
+        let mut contents = ImportContents::load(&mut *db.acquire().await?, import_id).await?;
 
-    query_builder.build().execute(&mut **txn).await?;
+        // Lessons at the same time come in no particular order
+        contents.lessons.sort_by_key(|l| format!("{l:?}"));
+        contents.availabilities.sort_by_key(|a| format!("{a:?}"));
 
-    Ok(())
+        Ok(contents)
+    }
+
+    #[sqlx::test]
+    async fn export_round_trip(db: PgPool) -> Result<()> {
+        let user_id = create_user(&db).await?;
+
+        let import_id = import_xml(&db, "orario.xml", SCHEDULE, user_id).await?;
+        let contents = load(&db, import_id).await?;
+
+        let exported = quick_xml::se::to_string(&ScheduleFile::from_contents(
+            ImportContents::load(&mut *db.acquire().await?, import_id).await?,
+        ))?;
+
+        let reimport_id = import_xml(&db, "export.xml", &exported, user_id).await?;
+        let reimported = load(&db, reimport_id).await?;
+
+        assert_eq!(contents.lessons.len(), 3);
+        assert_eq!(contents.availabilities.len(), 2);
+        assert!(contents.availabilities.iter().any(|a| {
+            a.room.as_deref() == Some("DISPOSIZIONE#2") && a.duration_minutes == 120
+        }));
+        assert_eq!(contents, reimported);
+
+        Ok(())
+    }
 }
@@ -1,17 +1,44 @@
+mod asc;
+mod csv_file;
+mod decode;
 mod importer;
+mod parser;
+mod preview;
+mod untis;
+pub mod upload;
+mod validation;
 
-use axum::{extract::Query, response::IntoResponse};
-use axum_serde::Xml;
-use chrono::NaiveDateTime;
-use http::StatusCode;
-use importer::import_file;
+pub use decode::MAX_FILE_SIZE;
+pub use importer::{ImportError, ScheduleFile, fail_interrupted_imports};
+
+use axum::{
+    body::Bytes,
+    extract::Query,
+    response::{IntoResponse, Response},
+};
+use axum_serde::Sonic;
+use chrono::{NaiveDate, NaiveDateTime};
+use http::{HeaderMap, StatusCode, header::CONTENT_TYPE};
+use importer::{import_file, queue_import};
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
 use tracing::error;
 use utoipa::{IntoParams, ToSchema};
 
 use crate::{
-    app::openapi::IMPORT_TAG, users::AuthSession,
-    web::endpoints::protected::import::post::importer::ScheduleFile,
+    app::openapi::IMPORT_TAG,
+    users::AuthSession,
+    web::endpoints::protected::import::{
+        carry_over::CarryOverReport,
+        overlap::OverlappingImport,
+        post::{
+            csv_file::CsvColumns,
+            decode::{ScheduleFormat, decode_schedule_file},
+            preview::ImportPreview,
+            untis::UntisOptions,
+            validation::ValidationReport,
+        },
+    },
 };
 
 #[derive(Debug, Deserialize, IntoParams)]
@@ -21,6 +48,27 @@ pub struct ImportFileMeta {
     mode: ImportMode,
     begin_ts: NaiveDateTime,
     end_ts: NaiveDateTime,
+    /// Any day of a week that is week A, for timetables alternating A/B weeks.
+    /// Leave empty if the timetable is the same every week.
+    week_a_start: Option<NaiveDate>,
+}
+
+impl ImportFileMeta {
+    pub fn new(
+        file_name: String,
+        mode: ImportMode,
+        begin_ts: NaiveDateTime,
+        end_ts: NaiveDateTime,
+        week_a_start: Option<NaiveDate>,
+    ) -> Self {
+        Self {
+            file_name,
+            mode,
+            begin_ts,
+            end_ts,
+            week_a_start,
+        }
+    }
 }
 
 #[derive(Default, Debug, Deserialize, Serialize, ToSchema)]
@@ -31,17 +79,60 @@ pub enum ImportMode {
     Write,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportOutcome {
+    /// Id of the created import, not set for dry runs
+    import_id: Option<i32>,
+    /// Warnings found in the file
+    report: ValidationReport,
+    /// Absences of other imports moved to the lessons of this one
+    carry_over: CarryOverReport,
+    /// What the import would create, only set for dry runs
+    preview: Option<ImportPreview>,
+    /// Other imports valid on some of the same days
+    overlaps: Vec<OverlappingImport>,
+}
+
+impl ImportOutcome {
+    pub fn import_id(&self) -> Option<i32> {
+        self.import_id
+    }
+}
+
+/// An import accepted for processing in the background
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportQueued {
+    /// Id of the pending import, its progress is given by `GET /import/{import_id}/status`
+    import_id: i32,
+    /// Other imports valid on some of the same days
+    overlaps: Vec<OverlappingImport>,
+}
+
 #[utoipa::path(
     post,
     path = "/",
     summary = "Import File",
-    description = "Import a schedule file. The file must be in XML format exported by OrarioFacile.",
-    request_body(content = ScheduleFile, content_type = "application/xml"),
-    params(ImportFileMeta),
+    description = "Import a schedule file. The file must be in XML format exported by OrarioFacile \
+                   or aSc Timetables, a CSV file whose columns are given by the CSV parameters, \
+                   or an Untis GPU001.TXT timetable, chosen by the content type. \
+                   Of Untis only GPU001 is read: GPU002 and the teacher and room files \
+                   (GPU004, GPU005) are not supported, so teachers and rooms keep their \
+                   short names and the times come from the Untis parameters. \
+                   Any of them can be compressed in a .zip or .gz. \
+                   The file is validated right away, then written in the background: \
+                   follow the returned import with `GET /import/{import_id}/status`. \
+                   Dry runs are processed within the request.",
+    request_body(content((ScheduleFile = "application/xml"), (String = "text/csv"), (String = "text/plain"))),
+    params(ImportFileMeta, CsvColumns, UntisOptions),
     responses(
-        (status = OK, description = "File imported successfully", example = ""),
+        (status = OK, description = "The preview of a dry run", body = ImportOutcome),
+        (status = ACCEPTED, description = "File queued for import", body = ImportQueued),
+        (status = BAD_REQUEST, description = "Invalid XML, CSV or Untis file", body = str, content_type = "text/plain"),
         (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
-        (status = INTERNAL_SERVER_ERROR, description = "Error importing file", example = "Error importing file: ..."),
+        (status = PAYLOAD_TOO_LARGE, description = "The file is larger than IMPORT_MAX_FILE_SIZE", body = str, content_type = "text/plain"),
+        (status = UNSUPPORTED_MEDIA_TYPE, description = "Unsupported content type or invalid archive", body = str, content_type = "text/plain"),
+        (status = UNPROCESSABLE_ENTITY, description = "The file is not valid", body = ValidationReport),
+        (status = INTERNAL_SERVER_ERROR, description = "Error importing file", example = "Error importing file"),
     ),
     security(
         ("session" = [])
@@ -51,23 +142,77 @@ pub enum ImportMode {
 pub async fn post(
     auth_session: AuthSession,
     Query(meta): Query<ImportFileMeta>,
-    Xml(file): Xml<ScheduleFile>,
+    Query(columns): Query<CsvColumns>,
+    Query(untis): Query<UntisOptions>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> impl IntoResponse {
     let Some(user) = auth_session.user else {
         return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
     };
 
-    match import_file(&auth_session.backend.db, meta, file, user.id).await {
-        Ok(_) => (),
-        Err(e) => {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+
+    let file = match ScheduleFormat::from_content_type(content_type)
+        .and_then(|format| decode_schedule_file(&body, format, &columns, &untis))
+    {
+        Ok(file) => file,
+        Err(e) => return e.into_response(),
+    };
+
+    import_or_queue(&auth_session.backend.db, meta, file, user.id).await
+}
+
+/// Imports a file read outside of a request, e.g. from the command line. The
+/// format is guessed from the file name, CSV and Untis files are read with the
+/// default options.
+pub async fn import_local_file(
+    db: &PgPool,
+    meta: ImportFileMeta,
+    bytes: &[u8],
+    user_id: i32,
+) -> Result<ImportOutcome, ImportError> {
+    let format = ScheduleFormat::from_file_name(&meta.file_name).unwrap_or(ScheduleFormat::Xml);
+    let file = decode_schedule_file(
+        bytes,
+        format,
+        &CsvColumns::default(),
+        &UntisOptions::default(),
+    )?;
+
+    import_file(db, meta, file, user_id).await
+}
+
+/// Dry runs are answered with their preview, writes are queued and answered
+/// with 202 once the file is validated.
+async fn import_or_queue(
+    db: &PgPool,
+    meta: ImportFileMeta,
+    file: ScheduleFile,
+    user_id: i32,
+) -> Response {
+    match meta.mode {
+        ImportMode::DryRun => match import_file(db, meta, file, user_id).await {
+            Ok(outcome) => Sonic(outcome).into_response(),
+            Err(e) => import_error_response(e),
+        },
+        ImportMode::Write => match queue_import(db, meta, file, user_id).await {
+            Ok(queued) => (StatusCode::ACCEPTED, Sonic(queued)).into_response(),
+            Err(e) => import_error_response(e),
+        },
+    }
+}
+
+fn import_error_response(e: ImportError) -> Response {
+    match e {
+        ImportError::Invalid(report) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, Sonic(report)).into_response()
+        }
+        e => {
             error!("Error importing file: {:?}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Error importing file: {:?}", e),
-            )
-                .into_response();
+            (StatusCode::INTERNAL_SERVER_ERROR, "Error importing file").into_response()
         }
     }
-
-    StatusCode::OK.into_response()
 }
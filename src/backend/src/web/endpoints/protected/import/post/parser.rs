@@ -0,0 +1,41 @@
+use chrono::NaiveTime;
+
+use crate::web::endpoints::protected::import::post::{decode::UploadError, importer::ScheduleFile};
+
+/// Subjects that mark availabilities in OrarioFacile exports, other formats
+/// use them too
+pub(super) const AVAILABILITY_SUBJECTS: [&str; 2] = ["DISPO", "RECUPERO_ORARIO"];
+
+/// Room given to the availabilities of the other formats, so that they are
+/// imported like the DISPOSIZIONE# slots of OrarioFacile
+pub(super) const DISPOSITION_ROOM: &str = "DISPOSIZIONE#";
+
+/// A timetable format. Parsers turn the decoded file into the lessons of an
+/// OrarioFacile export, which go through validation and `import_file` like
+/// any other file.
+pub trait ScheduleParser {
+    fn parse(&self, text: &str) -> Result<ScheduleFile, UploadError>;
+}
+
+/// The XML export of OrarioFacile
+pub struct OrarioFacileParser;
+
+impl ScheduleParser for OrarioFacileParser {
+    fn parse(&self, text: &str) -> Result<ScheduleFile, UploadError> {
+        Ok(quick_xml::de::from_str(text)?)
+    }
+}
+
+/// Formats minutes as the H:MM durations of OrarioFacile
+pub(super) fn format_duration(minutes: i64) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Parses times such as 8:00, 08:00 or 08:00:00
+pub(super) fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    let value = value.trim();
+
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .map_err(|_| format!("Invalid time: {value}"))
+}
@@ -0,0 +1,69 @@
+use color_eyre::Result;
+use serde::Serialize;
+use sqlx::PgConnection;
+use utoipa::ToSchema;
+
+use crate::web::endpoints::protected::import::{
+    contents::{ImportContents, ImportCounts},
+    diff::ImportDiff,
+    post::ImportFileMeta,
+};
+
+/// What an import would create, compared with the import currently in use.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportPreview {
+    counts: ImportCounts,
+    contents: ImportContents,
+    /// Id of the most recent import overlapping the same dates, if any
+    compared_with: Option<i32>,
+    /// Differences with the compared import
+    diff: Option<ImportDiff>,
+}
+
+impl ImportPreview {
+    /// Builds the preview of an import that has been written but not yet
+    /// committed.
+    pub async fn build(
+        conn: &mut PgConnection,
+        import_id: i32,
+        user_id: i32,
+        meta: &ImportFileMeta,
+    ) -> Result<Self> {
+        let contents = ImportContents::load(conn, import_id).await?;
+
+        let compared_with = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM import
+            WHERE user_id = $1
+              AND id <> $2
+              AND status = 'Done'
+              AND begin_ts <= $4
+              AND end_ts >= $3
+            ORDER BY import_ts DESC
+            LIMIT 1
+            "#,
+            user_id,
+            import_id,
+            meta.begin_ts,
+            meta.end_ts,
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let diff = match compared_with {
+            Some(id) => {
+                let old = ImportContents::load(conn, id).await?;
+                Some(ImportDiff::between(&old, &contents))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            counts: contents.counts(),
+            contents,
+            compared_with,
+            diff,
+        })
+    }
+}
@@ -0,0 +1,203 @@
+use ahash::AHashMap;
+use chrono::NaiveTime;
+use csv::ReaderBuilder;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::web::endpoints::protected::import::post::{
+    decode::UploadError,
+    importer::{ItaDay, RawLesson, ScheduleFile},
+    parser::{
+        AVAILABILITY_SUBJECTS, DISPOSITION_ROOM, ScheduleParser, format_duration, parse_time,
+    },
+};
+
+/// How to turn the Untis period numbers into times. Only GPU001 is read:
+/// GPU002 and the teacher and room files (GPU004, GPU005) are not supported,
+/// so the times come from here and teachers and rooms keep their short names.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(default)]
+pub struct UntisOptions {
+    /// Start times of the Untis periods from the first, separated by commas
+    #[param(default = "8:00,9:00,10:00,11:00,12:00,13:00,14:00,15:00")]
+    bell_schedule: String,
+    /// Length of a period in minutes
+    #[param(default = 60)]
+    period_minutes: i64,
+}
+
+impl Default for UntisOptions {
+    fn default() -> Self {
+        Self {
+            bell_schedule: "8:00,9:00,10:00,11:00,12:00,13:00,14:00,15:00".to_owned(),
+            period_minutes: 60,
+        }
+    }
+}
+
+impl UntisOptions {
+    fn start_times(&self) -> Result<Vec<NaiveTime>, UploadError> {
+        self.bell_schedule
+            .split(',')
+            .map(|time| parse_time(time).map_err(|e| UploadError::InvalidField("bell_schedule", e)))
+            .collect()
+    }
+
+    fn duration(&self) -> Result<String, UploadError> {
+        if self.period_minutes <= 0 {
+            return Err(UploadError::InvalidField(
+                "period_minutes",
+                "Must be positive".to_owned(),
+            ));
+        }
+
+        Ok(format_duration(self.period_minutes))
+    }
+}
+
+/// A period of a lesson, GPU001 has a line per teacher, class and room of it
+struct UntisSlot {
+    lesson: String,
+    day: String,
+    period: String,
+    subject: Option<String>,
+    teachers: Vec<String>,
+    classes: Vec<String>,
+    rooms: Vec<String>,
+}
+
+// GPU001.TXT, the timetable, without header:
+// lesson number;class;teacher;subject;room;day;period;
+// 138;"5A";"ROSSI";"INF";"07-TW";1;1;
+// 138;"5A";"BIANCHI";"INF";"07-TW";1;1;
+impl ScheduleParser for UntisOptions {
+    fn parse(&self, text: &str) -> Result<ScheduleFile, UploadError> {
+        let start_times = self.start_times()?;
+        let duration = self.duration()?;
+
+        // Untis uses ; by default but can be set to export with ,
+        let delimiter = match text.lines().next() {
+            Some(line) if !line.contains(';') && line.contains(',') => b',',
+            _ => b';',
+        };
+
+        let mut reader = ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+
+        let mut slots: Vec<UntisSlot> = Vec::new();
+        let mut slot_indexes: AHashMap<(String, String, String), usize> = AHashMap::new();
+
+        for record in reader.records() {
+            let record = record.map_err(|e| UploadError::InvalidUntis(e.to_string()))?;
+            let line = record.position().map_or(0, |p| p.line());
+
+            let field = |index: usize| {
+                record
+                    .get(index)
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+            };
+
+            if record.iter().all(|value| value.trim().is_empty()) {
+                continue;
+            }
+
+            let (Some(lesson), Some(day), Some(period)) = (field(0), field(5), field(6)) else {
+                return Err(UploadError::InvalidUntis(format!(
+                    "Line {line}: missing lesson number, day or period"
+                )));
+            };
+
+            // Lines of the same lesson and period are its teachers, classes
+            // and rooms
+            let index = *slot_indexes
+                .entry((lesson.to_owned(), day.to_owned(), period.to_owned()))
+                .or_insert_with(|| {
+                    slots.push(UntisSlot {
+                        lesson: lesson.to_owned(),
+                        day: day.to_owned(),
+                        period: period.to_owned(),
+                        subject: field(3).map(str::to_owned),
+                        teachers: Vec::new(),
+                        classes: Vec::new(),
+                        rooms: Vec::new(),
+                    });
+                    slots.len() - 1
+                });
+
+            let slot = &mut slots[index];
+
+            for (value, values) in [
+                (field(2), &mut slot.teachers),
+                (field(1), &mut slot.classes),
+                (field(4), &mut slot.rooms),
+            ] {
+                match value {
+                    Some(value) if !values.iter().any(|v| v == value) => {
+                        values.push(value.to_owned())
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut lessons = Vec::new();
+
+        for slot in slots {
+            let ita_day = ItaDay::try_from(slot.day.as_str()).map_err(UploadError::InvalidUntis)?;
+            let time = slot
+                .period
+                .parse::<usize>()
+                .ok()
+                .and_then(|period| period.checked_sub(1))
+                .and_then(|index| start_times.get(index))
+                .copied()
+                .ok_or_else(|| {
+                    UploadError::InvalidUntis(format!(
+                        "Period {} of lesson {} is not in the bell schedule",
+                        slot.period, slot.lesson
+                    ))
+                })?;
+
+            let is_availability = slot
+                .subject
+                .as_deref()
+                .is_some_and(|s| AVAILABILITY_SUBJECTS.contains(&s));
+
+            if is_availability {
+                // Availabilities are per teacher
+                for teacher in slot.teachers {
+                    lessons.push(RawLesson {
+                        duration: Some(duration.clone()),
+                        subject: slot.subject.clone(),
+                        teacher: Some(vec![teacher]),
+                        room: Some(vec![DISPOSITION_ROOM.to_owned()]),
+                        ita_day: Some(ita_day.clone()),
+                        time: Some(time),
+                        ..Default::default()
+                    });
+                }
+                continue;
+            }
+
+            let non_empty = |values: Vec<String>| (!values.is_empty()).then_some(values);
+
+            lessons.push(RawLesson {
+                duration: Some(duration.clone()),
+                subject: slot.subject,
+                teacher: non_empty(slot.teachers),
+                group: non_empty(slot.classes),
+                room: non_empty(slot.rooms),
+                ita_day: Some(ita_day),
+                time: Some(time),
+                ..Default::default()
+            });
+        }
+
+        Ok(ScheduleFile { lessons })
+    }
+}
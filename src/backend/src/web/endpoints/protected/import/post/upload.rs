@@ -0,0 +1,187 @@
+use std::{fmt::Display, str::FromStr};
+
+use axum::{
+    extract::{Multipart, Query, multipart::Field},
+    response::IntoResponse,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use http::StatusCode;
+use serde::{Deserialize, de::IntoDeserializer};
+use utoipa::ToSchema;
+
+use crate::{
+    app::openapi::IMPORT_TAG,
+    users::AuthSession,
+    web::endpoints::protected::import::post::{
+        ImportFileMeta, ImportMode, ImportOutcome, ImportQueued,
+        csv_file::CsvColumns,
+        decode::{MAX_FILE_SIZE, ScheduleFormat, UploadError, decode_schedule_file},
+        import_or_queue,
+        untis::UntisOptions,
+        validation::ValidationReport,
+    },
+};
+
+/// The same fields as the query parameters of the XML import, plus the file
+#[derive(Debug, Default, ToSchema)]
+pub struct ImportUploadForm {
+    /// The OrarioFacile, aSc Timetables or Untis export, or the CSV file, as is or compressed in a .zip or .gz
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+    /// From the file name, or the content type of the file when the name doesn't tell
+    #[schema(ignore)]
+    format: Option<ScheduleFormat>,
+    /// Defaults to the name of the uploaded file
+    file_name: Option<String>,
+    mode: Option<ImportMode>,
+    #[schema(required = true)]
+    begin_ts: Option<NaiveDateTime>,
+    #[schema(required = true)]
+    end_ts: Option<NaiveDateTime>,
+    week_a_start: Option<NaiveDate>,
+}
+
+impl ImportUploadForm {
+    async fn from_multipart(mut multipart: Multipart) -> Result<Self, UploadError> {
+        let mut form = Self::default();
+
+        while let Some(field) = multipart.next_field().await? {
+            match field.name() {
+                Some("file") => {
+                    if form.file_name.is_none() {
+                        form.file_name = field.file_name().map(str::to_owned);
+                    }
+                    form.format = Some(
+                        match field.file_name().and_then(ScheduleFormat::from_file_name) {
+                            Some(format) => format,
+                            None => ScheduleFormat::from_content_type(field.content_type())?,
+                        },
+                    );
+                    form.file = read_file(field).await?;
+                }
+                Some("file_name") => form.file_name = Some(field.text().await?),
+                Some("mode") => {
+                    let mode = field.text().await?;
+                    let de: serde::de::value::StrDeserializer<'_, serde::de::value::Error> =
+                        mode.as_str().into_deserializer();
+                    form.mode = Some(
+                        ImportMode::deserialize(de)
+                            .map_err(|e| UploadError::InvalidField("mode", e.to_string()))?,
+                    );
+                }
+                Some("begin_ts") => form.begin_ts = parse_field("begin_ts", field).await?,
+                Some("end_ts") => form.end_ts = parse_field("end_ts", field).await?,
+                Some("week_a_start") => {
+                    form.week_a_start = parse_field("week_a_start", field).await?
+                }
+                // Unknown fields are ignored, like unknown query parameters
+                _ => {}
+            }
+        }
+
+        if form.format.is_none() {
+            return Err(UploadError::MissingField("file"));
+        }
+
+        Ok(form)
+    }
+
+    fn into_parts(self) -> Result<(ImportFileMeta, ScheduleFormat, Vec<u8>), UploadError> {
+        let meta = ImportFileMeta {
+            file_name: self.file_name.ok_or(UploadError::MissingField("file_name"))?,
+            mode: self.mode.unwrap_or_default(),
+            begin_ts: self.begin_ts.ok_or(UploadError::MissingField("begin_ts"))?,
+            end_ts: self.end_ts.ok_or(UploadError::MissingField("end_ts"))?,
+            week_a_start: self.week_a_start,
+        };
+
+        let format = self.format.ok_or(UploadError::MissingField("file"))?;
+
+        Ok((meta, format, self.file))
+    }
+}
+
+/// Reads the file chunk by chunk, to stop as soon as it's too large.
+async fn read_file(mut field: Field<'_>) -> Result<Vec<u8>, UploadError> {
+    let mut file = Vec::new();
+
+    while let Some(chunk) = field.chunk().await? {
+        if file.len() + chunk.len() > *MAX_FILE_SIZE {
+            return Err(UploadError::TooLarge);
+        }
+        file.extend_from_slice(&chunk);
+    }
+
+    Ok(file)
+}
+
+/// Empty fields are treated as missing.
+async fn parse_field<T>(name: &'static str, field: Field<'_>) -> Result<Option<T>, UploadError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = field.text().await?;
+
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    value
+        .parse()
+        .map(Some)
+        .map_err(|e: T::Err| UploadError::InvalidField(name, e.to_string()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/upload",
+    summary = "Upload File",
+    description = "Import a schedule file sent as a form. The file must be exported by OrarioFacile \
+                   or aSc Timetables, a CSV file whose columns are given by the CSV parameters \
+                   or an Untis GPU001.TXT timetable, as is or compressed in a .zip or .gz. Windows-1252 files are converted to UTF-8. \
+                   Of Untis only GPU001 is read, GPU002 and the teacher and room files are not supported. \
+                   Like `POST /import`, writes are processed in the background.",
+    request_body(content = ImportUploadForm, content_type = "multipart/form-data"),
+    params(CsvColumns, UntisOptions),
+    responses(
+        (status = OK, description = "The preview of a dry run", body = ImportOutcome),
+        (status = ACCEPTED, description = "File queued for import", body = ImportQueued),
+        (status = BAD_REQUEST, description = "Missing or invalid form fields, or an invalid file", body = str, content_type = "text/plain"),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = PAYLOAD_TOO_LARGE, description = "The file is larger than IMPORT_MAX_FILE_SIZE", body = str, content_type = "text/plain"),
+        (status = UNSUPPORTED_MEDIA_TYPE, description = "Unsupported file type or invalid archive", body = str, content_type = "text/plain"),
+        (status = UNPROCESSABLE_ENTITY, description = "The file is not valid", body = ValidationReport),
+        (status = INTERNAL_SERVER_ERROR, description = "Error importing file", example = "Error importing file"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = IMPORT_TAG,
+)]
+pub async fn upload(
+    auth_session: AuthSession,
+    Query(columns): Query<CsvColumns>,
+    Query(untis): Query<UntisOptions>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let res = async {
+        let form = ImportUploadForm::from_multipart(multipart).await?;
+        let (meta, format, file) = form.into_parts()?;
+        let schedule_file = decode_schedule_file(&file, format, &columns, &untis)?;
+
+        Ok::<_, UploadError>((meta, schedule_file))
+    }
+    .await;
+
+    let (meta, schedule_file) = match res {
+        Ok(upload) => upload,
+        Err(e) => return e.into_response(),
+    };
+
+    import_or_queue(&auth_session.backend.db, meta, schedule_file, user.id).await
+}
@@ -0,0 +1,260 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    types::AvailabilityType,
+    web::endpoints::protected::import::post::importer::{
+        RawLesson, ScheduleFile, parse_duration,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// The file can be imported, but the result may not be what is expected
+    Warning,
+    /// The file cannot be imported
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueKind {
+    MissingDay,
+    MissingTime,
+    MissingDuration,
+    InvalidDuration,
+    InvalidWeek,
+    WeekWithoutStart,
+    LessonWithoutTeacher,
+    UnknownAvailabilitySubject,
+    AvailabilityTeacherCount,
+    AvailabilityOutsideDisposition,
+    EmptyFile,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValidationIssue {
+    /// Index of the offending <LESSON> in the file, starting from 0
+    lesson_index: Option<usize>,
+    severity: Severity,
+    kind: IssueKind,
+    message: String,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ValidationReport {
+    issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|i| i.severity == Severity::Error)
+    }
+
+    fn push(
+        &mut self,
+        lesson_index: Option<usize>,
+        severity: Severity,
+        kind: IssueKind,
+        message: impl Into<String>,
+    ) {
+        self.issues.push(ValidationIssue {
+            lesson_index,
+            severity,
+            kind,
+            message: message.into(),
+        });
+    }
+}
+
+/// Checks the whole file and collects every problem found, so that they can
+/// all be fixed in the export at once.
+pub fn validate(schedule_file: &ScheduleFile, week_a_start: Option<NaiveDate>) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    if schedule_file.lessons.is_empty() {
+        report.push(
+            None,
+            Severity::Error,
+            IssueKind::EmptyFile,
+            "The file doesn't contain any lesson",
+        );
+    }
+
+    for (index, lesson) in schedule_file.lessons.iter().enumerate() {
+        validate_lesson(index, lesson, &mut report);
+    }
+
+    // Without week A, lessons of both weeks are held every week until the
+    // weeks are set through PATCH /import/{id} or /import/{id}/weeks
+    let alternating = schedule_file
+        .lessons
+        .iter()
+        .filter(|l| matches!(l.week(), Ok(Some(_))))
+        .count();
+
+    if week_a_start.is_none() && alternating > 0 {
+        report.push(
+            None,
+            Severity::Warning,
+            IssueKind::WeekWithoutStart,
+            format!(
+                "{alternating} lessons belong to week A or B, but no week_a_start was given: they \
+                 will apply to every week until the weeks are set"
+            ),
+        );
+    }
+
+    report
+}
+
+fn validate_lesson(index: usize, lesson: &RawLesson, report: &mut ValidationReport) {
+    let index = Some(index);
+
+    if lesson.ita_day.is_none() {
+        report.push(
+            index,
+            Severity::Error,
+            IssueKind::MissingDay,
+            "The lesson doesn't have a day",
+        );
+    }
+
+    if lesson.time.is_none() {
+        report.push(
+            index,
+            Severity::Error,
+            IssueKind::MissingTime,
+            "The lesson doesn't have a time",
+        );
+    }
+
+    if let Err(e) = lesson.week() {
+        report.push(index, Severity::Error, IssueKind::InvalidWeek, e.to_string());
+    }
+
+    let teachers = lesson.teacher.as_ref().map_or(0, |t| t.len());
+
+    if lesson.is_disposition() {
+        let subject = lesson.subject.as_deref().unwrap_or_default();
+
+        if let Err(e) = AvailabilityType::try_from(subject) {
+            report.push(
+                index,
+                Severity::Error,
+                IssueKind::UnknownAvailabilitySubject,
+                e.to_string(),
+            );
+        }
+
+        if teachers != 1 {
+            report.push(
+                index,
+                Severity::Error,
+                IssueKind::AvailabilityTeacherCount,
+                format!("A DISPOSIZIONE must have exactly one teacher, found {teachers}"),
+            );
+        }
+
+        validate_duration(index, lesson, report);
+
+        return;
+    }
+
+    if lesson.is_availability() {
+        report.push(
+            index,
+            Severity::Warning,
+            IssueKind::AvailabilityOutsideDisposition,
+            "DISPO and RECUPERO_ORARIO outside a DISPOSIZIONE room are ignored",
+        );
+
+        return;
+    }
+
+    if teachers == 0 {
+        report.push(
+            index,
+            Severity::Error,
+            IssueKind::LessonWithoutTeacher,
+            "The lesson doesn't have a teacher",
+        );
+    }
+
+    validate_duration(index, lesson, report);
+}
+
+/// Lessons and availabilities are both stored with their duration
+fn validate_duration(index: Option<usize>, lesson: &RawLesson, report: &mut ValidationReport) {
+    match lesson.duration.as_deref() {
+        None => report.push(
+            index,
+            Severity::Warning,
+            IssueKind::MissingDuration,
+            "The lesson doesn't have a duration, it will last 1 hour",
+        ),
+        Some(duration) => match parse_duration(duration) {
+            Ok(d) if d.num_minutes() <= 0 => report.push(
+                index,
+                Severity::Error,
+                IssueKind::InvalidDuration,
+                format!("The duration must be positive: {duration}"),
+            ),
+            Ok(_) => {}
+            Err(e) => report.push(
+                index,
+                Severity::Error,
+                IssueKind::InvalidDuration,
+                e.to_string(),
+            ),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::*;
+    use crate::web::endpoints::protected::import::post::importer::ItaDay;
+
+    fn availability(duration: &str) -> RawLesson {
+        RawLesson {
+            duration: Some(duration.to_owned()),
+            subject: Some("DISPO".to_owned()),
+            teacher: Some(vec!["ROSSI MARIO".to_owned()]),
+            room: Some(vec!["DISPOSIZIONE#".to_owned()]),
+            ita_day: Some(ItaDay::Lun),
+            time: NaiveTime::from_hms_opt(8, 0, 0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn invalid_availability_duration() {
+        for duration in ["0:00", "1h", "1:xx"] {
+            let file = ScheduleFile {
+                lessons: vec![availability(duration)],
+            };
+
+            let report = validate(&file, None);
+
+            assert!(report.has_errors(), "{duration}");
+            assert!(
+                report
+                    .issues
+                    .iter()
+                    .any(|i| i.kind == IssueKind::InvalidDuration && i.lesson_index == Some(0)),
+                "{duration}"
+            );
+        }
+
+        let file = ScheduleFile {
+            lessons: vec![availability("1:00")],
+        };
+
+        assert!(!validate(&file, None).has_errors());
+    }
+}
@@ -0,0 +1,99 @@
+use axum::{extract::Path, response::IntoResponse};
+use axum_serde::Sonic;
+use chrono::NaiveDateTime;
+use color_eyre::Result;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    app::openapi::IMPORT_TAG,
+    types::ImportStatus,
+    users::AuthSession,
+    web::endpoints::protected::import::post::ImportOutcome,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImportStatusPathParams {
+    import_id: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ImportStatusInfo {
+    status: ImportStatus,
+    /// Why the import failed
+    error: Option<String>,
+    /// Set once the import is done
+    #[schema(value_type = Option<ImportOutcome>)]
+    outcome: Option<sonic_rs::Value>,
+    import_ts: NaiveDateTime,
+    finished_ts: Option<NaiveDateTime>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/{import_id}/status",
+    summary = "Get the status of an import",
+    description = "Imports are written in the background: poll this endpoint until the status \
+                   is done or failed.",
+    params(ImportStatusPathParams),
+    responses(
+        (status = OK, description = "Status of the import", body = ImportStatusInfo),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Import not found or not accessible"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = IMPORT_TAG,
+)]
+pub async fn status(
+    auth_session: AuthSession,
+    Path(path): Path<ImportStatusPathParams>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let res: Result<Option<ImportStatusInfo>> = async {
+        let row = sqlx::query!(
+            r#"
+            SELECT status AS "status: ImportStatus",
+                   error,
+                   outcome::text AS outcome,
+                   import_ts,
+                   finished_ts
+            FROM import
+            WHERE id = $1
+              AND user_id = $2
+            "#,
+            path.import_id,
+            user.id,
+        )
+        .fetch_optional(&auth_session.backend.db)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(ImportStatusInfo {
+            status: row.status,
+            error: row.error,
+            outcome: row.outcome.as_deref().map(sonic_rs::from_str).transpose()?,
+            import_ts: row.import_ts,
+            finished_ts: row.finished_ts,
+        }))
+    }
+    .await;
+
+    match res {
+        Ok(Some(info)) => Sonic(info).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Import not found").into_response(),
+        Err(e) => {
+            error!("Error when getting the status of the import: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
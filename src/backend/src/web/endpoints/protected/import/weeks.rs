@@ -0,0 +1,164 @@
+use ahash::AHashMap;
+use axum::{extract::Path, response::IntoResponse};
+use axum_serde::{
+    Sonic,
+    macros::{Deserialize, Serialize},
+};
+use chrono::{NaiveDate, Weekday};
+use http::StatusCode;
+use sqlx::{Postgres, QueryBuilder};
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{app::openapi::IMPORT_TAG, types::Week, users::AuthSession};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImportWeeksPathParams {
+    import_id: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ImportWeek {
+    /// Any day of the calendar week, stored as the Monday of that week
+    week_start: NaiveDate,
+    week: Week,
+}
+
+#[utoipa::path(
+    get,
+    path = "/{import_id}/weeks",
+    summary = "Get an import's week mapping",
+    description = "Calendar weeks explicitly mapped to week A or B. \
+                   Weeks not listed alternate from the import's week_a_start.",
+    params(ImportWeeksPathParams),
+    responses(
+        (status = OK, description = "The week mapping", body = Vec<ImportWeek>),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = IMPORT_TAG,
+)]
+pub async fn get_weeks(
+    auth_session: AuthSession,
+    Path(path): Path<ImportWeeksPathParams>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let weeks = match sqlx::query_as!(
+        ImportWeek,
+        r#"
+        SELECT iw.week_start, iw.week AS "week: Week"
+        FROM import_week iw
+                 JOIN import i ON iw.import_id = i.id
+        WHERE i.id = $1
+          AND i.user_id = $2
+        ORDER BY iw.week_start
+        "#,
+        path.import_id,
+        user.id,
+    )
+    .fetch_all(&auth_session.backend.db)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            error!("Database error when getting the import weeks: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+        }
+    };
+
+    Sonic(weeks).into_response()
+}
+
+#[utoipa::path(
+    put,
+    path = "/{import_id}/weeks",
+    summary = "Replace an import's week mapping",
+    params(ImportWeeksPathParams),
+    request_body = Vec<ImportWeek>,
+    responses(
+        (status = OK, description = "The week mapping was replaced"),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Import not found or not accessible"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = IMPORT_TAG,
+)]
+pub async fn put_weeks(
+    auth_session: AuthSession,
+    Path(path): Path<ImportWeeksPathParams>,
+    Sonic(req): Sonic<Vec<ImportWeek>>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let res: Result<bool, sqlx::Error> = async {
+        let mut txn = auth_session.backend.db.begin().await?;
+
+        let import = sqlx::query!(
+            r#"
+            SELECT id FROM import WHERE id = $1 AND user_id = $2
+            "#,
+            path.import_id,
+            user.id,
+        )
+        .fetch_optional(&mut *txn)
+        .await?;
+
+        if import.is_none() {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+            DELETE FROM import_week WHERE import_id = $1
+            "#,
+            path.import_id,
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        // Normalize to Mondays, the last entry wins if a week is given twice
+        let weeks: AHashMap<NaiveDate, Week> = req
+            .iter()
+            .map(|w| (w.week_start.week(Weekday::Mon).first_day(), w.week))
+            .collect();
+
+        if !weeks.is_empty() {
+            let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
+                r#"
+                INSERT INTO import_week (import_id, week_start, week)
+                "#,
+            );
+
+            query_builder.push_values(weeks, |mut b, (week_start, week)| {
+                b.push_bind(path.import_id);
+                b.push_bind(week_start);
+                b.push_bind(week);
+            });
+
+            query_builder.build().execute(&mut *txn).await?;
+        }
+
+        txn.commit().await?;
+
+        Ok(true)
+    }
+    .await;
+
+    match res {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Import not found").into_response(),
+        Err(e) => {
+            error!("Database error when replacing the import weeks: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
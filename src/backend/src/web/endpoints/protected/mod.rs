@@ -1,5 +1,6 @@
 mod absence;
 pub mod import;
+mod staff;
 mod teachers;
 
 use utoipa_axum::router::OpenApiRouter;
@@ -8,5 +9,6 @@ pub fn router() -> OpenApiRouter {
     OpenApiRouter::new()
         .nest("/absence", absence::router())
         .nest("/import", import::router())
+        .nest("/staff", staff::router())
         .nest("/teachers", teachers::router())
 }
@@ -0,0 +1,70 @@
+use axum::response::IntoResponse;
+use axum_serde::Sonic;
+use http::StatusCode;
+use serde::Serialize;
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::{app::openapi::STAFF_TAG, users::AuthSession};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StaffMember {
+    id: i32,
+    full_name: String,
+    email: Option<String>,
+    phone: Option<String>,
+    notes: Option<String>,
+    /// Normalized names the staff member appears with in the imports
+    aliases: Vec<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    summary = "List staff",
+    description = "Staff members are stable across imports, \
+                   imported teachers are linked to them by normalized name.",
+    responses(
+        (status = OK, description = "List of staff members", body = Vec<StaffMember>),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = STAFF_TAG,
+)]
+pub async fn get(auth_session: AuthSession) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let staff = match sqlx::query_as!(
+        StaffMember,
+        r#"
+        SELECT s.id,
+               s.full_name,
+               s.email,
+               s.phone,
+               s.notes,
+               ARRAY(SELECT sa.normalized_name
+                     FROM staff_alias sa
+                     WHERE sa.staff_id = s.id
+                     ORDER BY sa.normalized_name) AS "aliases!"
+        FROM staff s
+        WHERE s.user_id = $1
+        ORDER BY s.full_name
+        "#,
+        user.id,
+    )
+    .fetch_all(&auth_session.backend.db)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            error!("Database error when getting the staff: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response();
+        }
+    };
+
+    Sonic(staff).into_response()
+}
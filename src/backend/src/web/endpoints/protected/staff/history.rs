@@ -0,0 +1,187 @@
+use axum::{
+    extract::{Path, Query},
+    response::IntoResponse,
+};
+use axum_serde::Sonic;
+use chrono::{NaiveDate, NaiveTime};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    app::openapi::STAFF_TAG,
+    types::{AbsenceReason, AbsenceStatus},
+    users::AuthSession,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct StaffHistoryPathParams {
+    staff_id: i32,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct StaffHistoryQuery {
+    /// Only the absences with this reason
+    reason: Option<AbsenceReason>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StaffHistory {
+    stats: StaffStats,
+    /// Classes missed by the staff member, across every import
+    absences: Vec<StaffAbsence>,
+    /// Classes the staff member covered for someone else
+    substitutions: Vec<StaffSubstitution>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct StaffStats {
+    /// Number of imports the staff member appears in
+    imports: i64,
+    absences: usize,
+    /// Absences that were never covered
+    uncovered_absences: usize,
+    substitutions: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StaffAbsence {
+    absence_id: i32,
+    date: NaiveDate,
+    time: NaiveTime,
+    status: AbsenceStatus,
+    substitute_teacher: Option<String>,
+    reason: Option<AbsenceReason>,
+    notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StaffSubstitution {
+    absence_id: i32,
+    date: NaiveDate,
+    time: NaiveTime,
+    absent_teacher: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/{staff_id}/history",
+    summary = "History of a staff member",
+    description = "Absences and substitutions of a staff member across every import. \
+                   The absences can be filtered by reason.",
+    params(StaffHistoryPathParams, StaffHistoryQuery),
+    responses(
+        (status = OK, description = "Statistics, absences and substitutions", body = StaffHistory),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Staff member not found or not accessible"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = STAFF_TAG,
+)]
+pub async fn history(
+    auth_session: AuthSession,
+    Path(path): Path<StaffHistoryPathParams>,
+    Query(query): Query<StaffHistoryQuery>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let res: Result<Option<StaffHistory>, sqlx::Error> = async {
+        let mut conn = auth_session.backend.db.acquire().await?;
+
+        let imports = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(DISTINCT t.import_id) AS "imports!"
+            FROM staff s
+                     LEFT JOIN teacher t ON t.staff_id = s.id
+            WHERE s.id = $1
+              AND s.user_id = $2
+            GROUP BY s.id
+            "#,
+            path.staff_id,
+            user.id,
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let Some(imports) = imports else {
+            return Ok(None);
+        };
+
+        let absences = sqlx::query_as!(
+            StaffAbsence,
+            r#"
+            SELECT ab.id           AS absence_id,
+                   ab.absence_date AS date,
+                   l.time::time    AS "time!",
+                   ab.status       AS "status: AbsenceStatus",
+                   st.full_name    AS "substitute_teacher?",
+                   p.reason        AS "reason?: AbsenceReason",
+                   p.notes         AS "notes?"
+            FROM absence ab
+                     JOIN teacher t ON ab.absent_teacher = t.id
+                     JOIN lesson l ON ab.absent_teacher_lesson = l.id
+                     LEFT JOIN availability av ON ab.substitute_teacher_availability = av.id
+                     LEFT JOIN teacher st ON av.teacher_id = st.id
+                     LEFT JOIN absence_period p ON ab.period_id = p.id
+            WHERE t.staff_id = $1
+              AND ($2::absence_reason IS NULL OR p.reason = $2)
+            ORDER BY ab.absence_date DESC, l.time
+            "#,
+            path.staff_id,
+            query.reason as Option<AbsenceReason>,
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let substitutions = sqlx::query_as!(
+            StaffSubstitution,
+            r#"
+            SELECT ab.id           AS absence_id,
+                   ab.absence_date AS date,
+                   l.time::time    AS "time!",
+                   abt.full_name   AS absent_teacher
+            FROM absence ab
+                     JOIN availability av ON ab.substitute_teacher_availability = av.id
+                     JOIN teacher st ON av.teacher_id = st.id
+                     JOIN teacher abt ON ab.absent_teacher = abt.id
+                     JOIN lesson l ON ab.absent_teacher_lesson = l.id
+            WHERE st.staff_id = $1
+            ORDER BY ab.absence_date DESC, l.time
+            "#,
+            path.staff_id,
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let stats = StaffStats {
+            imports,
+            absences: absences.len(),
+            uncovered_absences: absences
+                .iter()
+                .filter(|a| a.status == AbsenceStatus::Uncovered)
+                .count(),
+            substitutions: substitutions.len(),
+        };
+
+        Ok(Some(StaffHistory {
+            stats,
+            absences,
+            substitutions,
+        }))
+    }
+    .await;
+
+    match res {
+        Ok(Some(history)) => Sonic(history).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Staff member not found").into_response(),
+        Err(e) => {
+            error!("Database error when getting the staff history: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use axum::{extract::Path, response::IntoResponse};
+use axum_serde::{Sonic, macros::Deserialize};
+use http::StatusCode;
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{app::openapi::STAFF_TAG, users::AuthSession};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct StaffMergePathParams {
+    /// The staff member that is kept
+    staff_id: i32,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StaffMergeRequest {
+    /// The staff member merged into the kept one and then deleted
+    other_staff_id: i32,
+}
+
+#[utoipa::path(
+    post,
+    path = "/{staff_id}/merge",
+    summary = "Merge two staff members",
+    description = "Merge a staff member into another one, e.g. ROSSI M. into ROSSI MARIO. \
+                   Teachers and aliases are moved to the kept staff member, \
+                   missing contact details are taken from the merged one.",
+    params(StaffMergePathParams),
+    request_body = StaffMergeRequest,
+    responses(
+        (status = OK, description = "The staff members were merged"),
+        (status = BAD_REQUEST, description = "A staff member cannot be merged into itself"),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Staff member not found or not accessible"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = STAFF_TAG,
+)]
+pub async fn merge(
+    auth_session: AuthSession,
+    Path(path): Path<StaffMergePathParams>,
+    Sonic(req): Sonic<StaffMergeRequest>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    if path.staff_id == req.other_staff_id {
+        return (
+            StatusCode::BAD_REQUEST,
+            "A staff member cannot be merged into itself",
+        )
+            .into_response();
+    }
+
+    let res: Result<bool, sqlx::Error> = async {
+        let mut txn = auth_session.backend.db.begin().await?;
+
+        let merged = sqlx::query!(
+            r#"
+            UPDATE staff kept
+            SET email = COALESCE(kept.email, other.email),
+                phone = COALESCE(kept.phone, other.phone),
+                notes = COALESCE(kept.notes, other.notes)
+            FROM staff other
+            WHERE kept.id = $1
+              AND other.id = $2
+              AND kept.user_id = $3
+              AND other.user_id = $3
+            "#,
+            path.staff_id,
+            req.other_staff_id,
+            user.id,
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        if merged.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE teacher SET staff_id = $1 WHERE staff_id = $2
+            "#,
+            path.staff_id,
+            req.other_staff_id,
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE staff_alias SET staff_id = $1 WHERE staff_id = $2
+            "#,
+            path.staff_id,
+            req.other_staff_id,
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        // The absences of both are kept, under the remaining staff member
+        sqlx::query!(
+            r#"
+            UPDATE absence_period SET staff_id = $1 WHERE staff_id = $2
+            "#,
+            path.staff_id,
+            req.other_staff_id,
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM staff WHERE id = $1
+            "#,
+            req.other_staff_id,
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(true)
+    }
+    .await;
+
+    match res {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Staff member not found").into_response(),
+        Err(e) => {
+            error!("Database error when merging the staff members: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+mod get;
+mod history;
+mod merge;
+mod patch;
+mod split;
+
+pub fn router() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(get::get))
+        .routes(routes!(patch::patch))
+        .routes(routes!(merge::merge))
+        .routes(routes!(split::split))
+        .routes(routes!(history::history))
+}
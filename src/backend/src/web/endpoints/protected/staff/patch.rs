@@ -0,0 +1,73 @@
+use axum::{extract::Path, response::IntoResponse};
+use axum_serde::{Sonic, macros::Deserialize};
+use http::StatusCode;
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{app::openapi::STAFF_TAG, users::AuthSession};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct StaffPatchPathParams {
+    staff_id: i32,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StaffPatchRequest {
+    full_name: Option<String>,
+    email: Option<String>,
+    phone: Option<String>,
+    notes: Option<String>,
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{staff_id}",
+    summary = "Modify a staff member",
+    params(StaffPatchPathParams),
+    request_body = StaffPatchRequest,
+    responses(
+        (status = OK, description = "The staff member was patched"),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Staff member not found or not accessible"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = STAFF_TAG,
+)]
+pub async fn patch(
+    auth_session: AuthSession,
+    Path(path): Path<StaffPatchPathParams>,
+    Sonic(req): Sonic<StaffPatchRequest>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    match sqlx::query!(
+        r#"
+        UPDATE staff
+        SET full_name = COALESCE($3, full_name),
+            email = COALESCE($4, email),
+            phone = COALESCE($5, phone),
+            notes = COALESCE($6, notes)
+        WHERE id = $1 AND user_id = $2
+        "#,
+        path.staff_id,
+        user.id,
+        req.full_name,
+        req.email,
+        req.phone,
+        req.notes,
+    )
+    .execute(&auth_session.backend.db)
+    .await
+    {
+        Ok(done) if done.rows_affected() >= 1 => StatusCode::OK.into_response(),
+        Ok(_) => (StatusCode::NOT_FOUND, "Staff member not found").into_response(),
+        Err(e) => {
+            error!("Database error when patching the staff member: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use axum::{extract::Path, response::IntoResponse};
+use axum_serde::{
+    Sonic,
+    macros::{Deserialize, Serialize},
+};
+use http::StatusCode;
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{app::openapi::STAFF_TAG, users::AuthSession};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct StaffSplitPathParams {
+    staff_id: i32,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StaffSplitRequest {
+    /// Aliases moved to the new staff member, with their teachers
+    aliases: Vec<String>,
+    /// Name of the new staff member, defaults to the first alias
+    full_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StaffSplitResponse {
+    /// Id of the new staff member
+    staff_id: i32,
+}
+
+enum SplitOutcome {
+    Split(i32),
+    NotFound,
+    Invalid(&'static str),
+}
+
+#[utoipa::path(
+    post,
+    path = "/{staff_id}/split",
+    summary = "Split a staff member",
+    description = "Move some aliases of a staff member, and the teachers imported with them, \
+                   to a new staff member. Undoes a wrong merge.",
+    params(StaffSplitPathParams),
+    request_body = StaffSplitRequest,
+    responses(
+        (status = OK, description = "The new staff member", body = StaffSplitResponse),
+        (status = BAD_REQUEST, description = "The aliases are not valid"),
+        (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
+        (status = NOT_FOUND, description = "Staff member not found or not accessible"),
+    ),
+    security(
+        ("session" = [])
+    ),
+    tag = STAFF_TAG,
+)]
+pub async fn split(
+    auth_session: AuthSession,
+    Path(path): Path<StaffSplitPathParams>,
+    Sonic(req): Sonic<StaffSplitRequest>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    };
+
+    let Some(first_alias) = req.aliases.first() else {
+        return (StatusCode::BAD_REQUEST, "At least one alias must be moved").into_response();
+    };
+
+    let full_name = req.full_name.clone().unwrap_or_else(|| first_alias.clone());
+
+    let res: Result<SplitOutcome, sqlx::Error> = async {
+        let mut txn = auth_session.backend.db.begin().await?;
+
+        let aliases = sqlx::query!(
+            r#"
+            SELECT COUNT(*)                                                  AS "total!",
+                   COUNT(*) FILTER (WHERE sa.normalized_name IN
+                                          (SELECT normalize_name(a) FROM UNNEST($3::text[]) a)) AS "moved!"
+            FROM staff s
+                     JOIN staff_alias sa ON sa.staff_id = s.id
+            WHERE s.id = $1
+              AND s.user_id = $2
+            "#,
+            path.staff_id,
+            user.id,
+            &req.aliases,
+        )
+        .fetch_one(&mut *txn)
+        .await?;
+
+        if aliases.total == 0 {
+            return Ok(SplitOutcome::NotFound);
+        }
+
+        if aliases.moved == 0 {
+            return Ok(SplitOutcome::Invalid(
+                "None of the aliases belongs to the staff member",
+            ));
+        }
+
+        if aliases.moved == aliases.total {
+            return Ok(SplitOutcome::Invalid(
+                "At least one alias must stay with the staff member",
+            ));
+        }
+
+        let new_staff_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO staff (user_id, full_name)
+            VALUES ($1, $2)
+            RETURNING id
+            "#,
+            user.id,
+            full_name,
+        )
+        .fetch_one(&mut *txn)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE staff_alias
+            SET staff_id = $2
+            WHERE staff_id = $1
+              AND normalized_name IN (SELECT normalize_name(a) FROM UNNEST($3::text[]) a)
+            "#,
+            path.staff_id,
+            new_staff_id,
+            &req.aliases,
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE teacher t
+            SET staff_id = $2
+            FROM staff_alias sa
+            WHERE t.staff_id = $1
+              AND sa.staff_id = $2
+              AND sa.user_id = $3
+              AND sa.normalized_name = normalize_name(t.full_name)
+            "#,
+            path.staff_id,
+            new_staff_id,
+            user.id,
+        )
+        .execute(&mut *txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(SplitOutcome::Split(new_staff_id))
+    }
+    .await;
+
+    match res {
+        Ok(SplitOutcome::Split(staff_id)) => Sonic(StaffSplitResponse { staff_id }).into_response(),
+        Ok(SplitOutcome::NotFound) => {
+            (StatusCode::NOT_FOUND, "Staff member not found").into_response()
+        }
+        Ok(SplitOutcome::Invalid(message)) => (StatusCode::BAD_REQUEST, message).into_response(),
+        Err(e) => {
+            error!("Database error when splitting the staff member: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+        }
+    }
+}
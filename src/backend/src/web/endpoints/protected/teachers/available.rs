@@ -17,6 +17,21 @@ pub struct AvailableTeacher {
     id: i32,
     full_name: String,
     availability_type: AvailabilityType,
+    /// Whether the teacher also teaches the subject of the absent lesson
+    teaches_subject: bool,
+    /// Why the teacher can't cover the lesson after all, if any
+    conflict: Option<AvailabilityConflict>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum AvailabilityConflict {
+    /// The teacher is absent at that time too
+    Absent,
+    /// The teacher already covers another class at that time
+    Substituting,
+    /// The teacher has a lesson at that time
+    Teaching,
 }
 
 #[utoipa::path(
@@ -25,7 +40,7 @@ pub struct AvailableTeacher {
     summary = "Available teachers for an absence",
     params(GetCanBeAbsentRequest),
     responses(
-        (status = OK, description = "Available Teachers and their availability type", body = Vec<AvailableTeacher>),
+        (status = OK, description = "Available Teachers and their availability type, those with a conflict last", body = Vec<AvailableTeacher>),
         (status = UNAUTHORIZED, description = "Unauthorized", example = "Unauthorized"),
     ),
     security(
@@ -41,23 +56,61 @@ pub async fn available(
         return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
     };
 
-    let available_teachers = match sqlx::query_as!(
-        AvailableTeacher,
+    let rows = match sqlx::query!(
         r#"
         SELECT DISTINCT teacher.id,
                 teacher.full_name,
-                availability.availability_type as "availability_type: AvailabilityType"
+                availability.availability_type as "availability_type: AvailabilityType",
+                EXISTS (SELECT 1
+                        FROM lesson_teacher taught
+                                 JOIN lesson taught_lesson ON taught_lesson.id = taught.lesson_id
+                                 JOIN subject taught_subject ON taught_subject.id = taught_lesson.subject_id
+                        WHERE taught.teacher_id = teacher.id
+                          AND taught_subject.name = absent_subject.name) as "teaches_subject!",
+                (EXISTS (SELECT 1
+                         FROM absence other
+                                  JOIN lesson other_lesson ON other.absent_teacher_lesson = other_lesson.id
+                         WHERE other.absent_teacher = teacher.id
+                           AND other.absence_date = absence.absence_date
+                           AND other_lesson.time::time < absent_lesson.time::time + absent_lesson.duration
+                           AND other_lesson.time::time + other_lesson.duration > absent_lesson.time::time)
+                    OR EXISTS (SELECT 1
+                               FROM absence_period period
+                               WHERE period.staff_id = teacher.staff_id
+                                 AND absence.absence_date BETWEEN period.begin_date AND period.end_date
+                                 AND (period.begin_time IS NULL
+                                   OR (period.begin_time < absent_lesson.time::time + absent_lesson.duration
+                                       AND period.end_time > absent_lesson.time::time)))) as "absent!",
+                EXISTS (SELECT 1
+                        FROM absence other
+                                 JOIN availability other_availability
+                                      ON other.substitute_teacher_availability = other_availability.id
+                                 JOIN lesson other_lesson ON other.absent_teacher_lesson = other_lesson.id
+                        WHERE other_availability.teacher_id = teacher.id
+                          AND other.id <> absence.id
+                          AND other.absence_date = absence.absence_date
+                          AND other_lesson.time::time < absent_lesson.time::time + absent_lesson.duration
+                          AND other_lesson.time::time + other_lesson.duration > absent_lesson.time::time) as "substituting!",
+                EXISTS (SELECT 1
+                        FROM lesson_teacher own
+                                 JOIN lesson own_lesson ON own_lesson.id = own.lesson_id
+                        WHERE own.teacher_id = teacher.id
+                          AND own_lesson.day = absent_lesson.day
+                          AND own_lesson.time::time < absent_lesson.time::time + absent_lesson.duration
+                          AND own_lesson.time::time + own_lesson.duration > absent_lesson.time::time
+                          AND week_applies(own_lesson.week, teacher.import_id, absence.absence_date)) as "teaching!"
         FROM absence
                  JOIN lesson absent_lesson ON absence.absent_teacher_lesson = absent_lesson.id
-                 JOIN teacher absent_teacher ON absent_teacher.id = absent_lesson.teacher_id
-                 JOIN import active_import ON active_import.id = absent_teacher.import_id
-            AND absence.absence_date BETWEEN active_import.begin_ts AND active_import.end_ts
-                 JOIN teacher ON teacher.import_id = active_import.id
+                 JOIN teacher absent_teacher ON absent_teacher.id = absence.absent_teacher
+                 LEFT JOIN subject absent_subject ON absent_subject.id = absent_lesson.subject_id
+                 JOIN import absence_import ON absence_import.id = absent_teacher.import_id
+                 JOIN teacher ON teacher.import_id = active_import($1, absence.absence_date)
                  JOIN availability ON availability.teacher_id = teacher.id
         WHERE absence.id = $2
           AND availability.day = absent_lesson.day
           AND availability.time = absent_lesson.time
-          AND active_import.user_id = $1
+          AND absence_import.user_id = $1
+          AND week_applies(availability.week, teacher.import_id, absence.absence_date)
         "#,
         user.id,
         req.absence_id,
@@ -72,5 +125,38 @@ pub async fn available(
         }
     };
 
+    let mut available_teachers: Vec<AvailableTeacher> = rows
+        .into_iter()
+        .map(|row| {
+            let conflict = if row.absent {
+                Some(AvailabilityConflict::Absent)
+            } else if row.substituting {
+                Some(AvailabilityConflict::Substituting)
+            } else if row.teaching {
+                Some(AvailabilityConflict::Teaching)
+            } else {
+                None
+            };
+
+            AvailableTeacher {
+                id: row.id,
+                full_name: row.full_name,
+                availability_type: row.availability_type,
+                teaches_subject: row.teaches_subject,
+                conflict,
+            }
+        })
+        .collect();
+
+    // Teachers who can actually cover the lesson first, then those of the
+    // same subject, so they can carry on with the lesson
+    available_teachers.sort_unstable_by(|a, b| {
+        a.conflict
+            .is_some()
+            .cmp(&b.conflict.is_some())
+            .then_with(|| b.teaches_subject.cmp(&a.teaches_subject))
+            .then_with(|| a.full_name.cmp(&b.full_name))
+    });
+
     Sonic(available_teachers).into_response()
 }
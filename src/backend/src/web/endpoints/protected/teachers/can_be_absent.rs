@@ -1,16 +1,17 @@
 use axum::{extract::Query, response::IntoResponse};
 use axum_serde::Sonic;
-use chrono::NaiveDate;
+use chrono::{Local, NaiveDate};
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 
-use crate::{app::openapi::DASHBOARD_TAG, users::AuthSession};
+use crate::{app::openapi::DASHBOARD_TAG, types::IsoDow, users::AuthSession};
 
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct GetCanBeAbsentRequest {
     /// Date for which to get teachers who can be absent.
-    /// Results in the teachers who have lessons on that day.
+    /// Results in the teachers who have lessons on that day, in the import
+    /// active on that day.
     /// If not provided, defaults to today.
     date: Option<NaiveDate>,
 }
@@ -43,19 +44,23 @@ pub async fn can_be_absent(
         return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
     };
 
+    let date = req.date.unwrap_or_else(|| Local::now().date_naive());
+
     let can_be_absent_teachers = match sqlx::query_as!(
         CanBeAbsentTeacher,
         r#"
         SELECT DISTINCT t.id, t.full_name
         FROM teacher t
-                 JOIN import i ON t.import_id = i.id
-                 JOIN lesson l ON t.id = l.teacher_id
-        WHERE l.day = EXTRACT(DOW FROM COALESCE($1, CURRENT_DATE)::date)
-          AND i.user_id = $2
+                 JOIN lesson_teacher lt ON t.id = lt.teacher_id
+                 JOIN lesson l ON lt.lesson_id = l.id
+        WHERE l.day = $3::smallint
+          AND t.import_id = active_import($2, $1)
+          AND week_applies(l.week, t.import_id, $1)
         ORDER BY t.full_name
         "#,
-        req.date,
+        date,
         user.id,
+        IsoDow::from(date).iso_dow(),
     )
     .fetch_all(&auth_session.backend.db)
     .await